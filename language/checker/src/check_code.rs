@@ -16,21 +16,40 @@ pub async fn verify_code(process_manager: &TypesChecker, resolver: &Box<dyn Name
                          syntax: &Arc<Mutex<Syntax>>, variables: &mut SimpleVariableManager, references: bool, top: bool) -> Result<FinalizedCodeBody, ParsingError> {
     let mut body = Vec::new();
     let mut found_end = false;
-    for line in code.expressions {
+    // Set after a break or continue, until the next labeled block which can be jumped to.
+    let mut jumped = false;
+    let mut lines = code.expressions.into_iter();
+    while let Some(line) = lines.next() {
         match &line.effect {
             Effects::CompareJump(_, _, _) => found_end = true,
             Effects::Jump(_) => found_end = true,
+            Effects::CodeBody(_) => jumped = false,
             _ => {}
         }
 
+        if jumped && is_statement(&line.effect) {
+            warn_unreachable("break or continue", syntax);
+            jumped = false;
+        }
+        if let (ExpressionType::Line, Effects::Jump(_)) = (&line.expression_type, &line.effect) {
+            jumped = true;
+        }
+
         body.push(FinalizedExpression::new(line.expression_type,
                                            verify_effect(process_manager, resolver.boxed_clone(),
                                                          line.effect, return_type, syntax, variables, references).await?));
 
+        if let ExpressionType::Line = line.expression_type {
+            check_must_use(&body.last().unwrap().effect, syntax);
+        }
+
         if let ExpressionType::Return = line.expression_type {
             if let Some(return_type) = return_type {
                 let mut last = body.pop().unwrap();
-                let last_type = last.effect.get_return(variables).unwrap();
+                let last_type = match last.effect.get_return(variables) {
+                    Some(found) => found,
+                    None => return Err(placeholder_error(format!("Expected a {} to be returned!", return_type)))
+                };
                 // Only downcast types that don't match and aren't generic
                 if last_type != *return_type && last_type.name_safe().is_some() {
                     if last_type.of_type(return_type, syntax.clone()).await {
@@ -47,20 +66,27 @@ pub async fn verify_code(process_manager: &TypesChecker, resolver: &Box<dyn Name
                     }
                 }
                 body.push(last);
+            } else if let Some(found) = body.last().unwrap().effect.get_return(variables) {
+                return Err(placeholder_error(format!("Can't return a {} from a function without a return type!", found)));
+            }
+            // Nothing after a return is compiled, so tell the user instead of dropping it silently.
+            if lines.any(|line| is_statement(&line.effect)) {
+                warn_unreachable("return", syntax);
             }
             return Ok(FinalizedCodeBody::new(body, code.label.clone(), true));
         }
     }
 
+    // LLVM requires every block to end in a branch, so catch it here instead of failing to verify the module.
     if !found_end && !top {
-        panic!("Code body with label {} doesn't return or jump!", code.label)
+        return Err(placeholder_error(format!("Code block {} must end in a return or jump!", code.label)));
     }
 
     return Ok(FinalizedCodeBody::new(body, code.label.clone(), false));
 }
 
 #[async_recursion]
-async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameResolver>, effect: Effects, return_type: &Option<FinalizedTypes>,
+pub async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameResolver>, effect: Effects, return_type: &Option<FinalizedTypes>,
                        syntax: &Arc<Mutex<Syntax>>, variables: &mut SimpleVariableManager, references: bool) -> Result<FinalizedEffects, ParsingError> {
     let output = match effect {
         Effects::Paren(inner) => verify_effect(process_manager, resolver, *inner, return_type, syntax, variables, references).await?,
@@ -94,7 +120,8 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
                         let combined =
                             operation[0..operation.len() - 2].to_string() + &inner_operation;
                         let new_operation = if operation.starts_with("{}") && inner_operation.ends_with("{}") {
-                            let mut output = vec!();
+                            // Try the whole thing first for operations with three values, like {}[{}]={}
+                            let mut output = vec!(combined.clone());
                             for i in 0..combined.len() - operation.len() - 2 {
                                 let mut temp = combined.clone();
                                 temp.truncate(operation.len() + i);
@@ -201,11 +228,16 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
                 }.await?
             };
 
-            if Attribute::find_attribute("operation", &operation.attributes).unwrap().as_string_attribute().unwrap().contains("{+}") {
+            let template = Attribute::find_attribute("operation", &operation.attributes).unwrap().as_string_attribute().unwrap();
+            if template.contains("{+}") {
                 if let Effects::CreateArray(_) = values.get(0).unwrap() {} else {
                     let effect = Effects::CreateArray(vec!(values.remove(0)));
                     values.push(effect);
                 }
+            } else if template.matches("{}").count() != values.len() {
+                // Each {} in the operation is one value, so any other amount can't match the trait's function.
+                return Err(placeholder_error(format!("Operation {} takes {} values, but was given {}!",
+                                                     template, template.matches("{}").count(), values.len())));
             }
 
             let calling;
@@ -215,10 +247,11 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
                 calling = Box::new(Effects::NOP());
             }
 
-            verify_effect(process_manager, resolver,
-                          Effects::ImplementationCall(calling, operation.name.clone(),
-                                                      String::new(), values, None),
-                          return_type, syntax, variables, references).await?
+            let output = verify_effect(process_manager, resolver,
+                                       Effects::ImplementationCall(calling, operation.name.clone(),
+                                                                   String::new(), values, None),
+                                       return_type, syntax, variables, references).await?;
+            fold_constant(output)?
         }
         Effects::ImplementationCall(calling, traits, method, effects, returning) => {
             let mut finalized_effects = Vec::new();
@@ -388,7 +421,13 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
                 }
 
                 finalized_effects.insert(0, calling);
+                // Methods declared in a structure's body are named after the structure, so check there first.
+                let structure_method = format!("{}::{}",
+                                               return_type.inner_struct().data.name.split('$').next().unwrap(), method);
                 if let Ok(value) = Syntax::get_function(syntax.clone(), placeholder_error(String::new()),
+                                                        structure_method, resolver.boxed_clone(), true).await {
+                    value
+                } else if let Ok(value) = Syntax::get_function(syntax.clone(), placeholder_error(String::new()),
                                                         method.clone(), resolver.boxed_clone(), true).await {
                     value
                 } else {
@@ -406,14 +445,15 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
                         check_method(process_manager, AsyncDataGetter::new(syntax.clone(), method).await,
                                      effects.clone(), syntax, variables, resolver_ref, returning.clone()).await
                     };
-                    return TraitImplWaiter {
+                    return fold_constant(TraitImplWaiter {
                         syntax: syntax.clone(),
                         resolver: resolver.boxed_clone(),
                         method: method.clone(),
                         return_type: return_type.clone(),
                         checker,
-                        error: placeholder_error(format!("Unknown method {}", method)),
-                    }.await;
+                        error: placeholder_error(format!("{} doesn't implement any imported trait with the method {}!",
+                                                         return_type, method)),
+                    }.await?);
                 }
             } else {
                 Syntax::get_function(syntax.clone(), placeholder_error(format!("Unknown method {}", method)),
@@ -427,7 +467,7 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
             };
 
             let method = AsyncDataGetter::new(syntax.clone(), method).await;
-            check_method(process_manager, method, finalized_effects, syntax, variables, &resolver, returning).await?
+            fold_constant(check_method(process_manager, method, finalized_effects, syntax, variables, &resolver, returning).await?)?
         }
         Effects::CompareJump(effect, first, second) =>
             FinalizedEffects::CompareJump(Box::new(
@@ -437,6 +477,7 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
             let target = Syntax::parse_type(syntax.clone(), placeholder_error(format!("Test")),
                                             resolver.boxed_clone(), target, vec!())
                 .await?.finalize(syntax.clone()).await;
+            check_struct_bounds(&target, syntax).await?;
             let mut final_effects = Vec::new();
             for (field_name, effect) in effects {
                 let mut i = 0;
@@ -455,6 +496,17 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
                 final_effects.push((i, verify_effect(process_manager, resolver.boxed_clone(), effect, return_type, syntax, variables, references).await?));
             }
 
+            // Fill in any fields that weren't given with their default values.
+            for (i, field) in target.get_fields().iter().enumerate() {
+                if final_effects.iter().any(|(found, _)| *found == i) {
+                    continue;
+                }
+                match &field.default {
+                    Some(default) => final_effects.push((i, default.clone())),
+                    None => return Err(placeholder_error(format!("Missing field {}!", field.field.name)))
+                }
+            }
+
             FinalizedEffects::CreateStruct(Some(Box::new(FinalizedEffects::HeapAllocate(target.clone()))),
                                            target, final_effects)
         }
@@ -491,6 +543,12 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
                                           return_type, syntax, variables, references).await?);
             }
 
+            // The element type comes from the first element, so an empty array has nothing to infer it from.
+            if output.is_empty() {
+                return Err(placeholder_error(
+                    "Can't infer the type of an empty array, use array::empty<T>() instead!".to_string()));
+            }
+
             let types = output.get(0).map(|found| found.get_return(variables).unwrap());
             if let Some(found) = &types {
                 for checking in &output {
@@ -507,6 +565,120 @@ async fn verify_effect(process_manager: &TypesChecker, resolver: Box<dyn NameRes
     return Ok(output);
 }
 
+/// Folds addition, multiplication and casts of literals into a single literal, so a constant that overflows
+/// the type it's calculated in is an error instead of silently wrapping at runtime.
+/// Subtraction and negation aren't checked, since integer literals are u64s and negative numbers like 0 - 1 wrap into them.
+fn fold_constant(effect: FinalizedEffects) -> Result<FinalizedEffects, ParsingError> {
+    let value = constant_value(&effect)?;
+    return Ok(match (value, &effect) {
+        // Literals are always u64s, so other types are only checked instead of being replaced by a literal.
+        (Some(value), FinalizedEffects::MethodCall(_, method, _))
+        if method.return_type.as_ref().map(FinalizedTypes::name) == Some("u64".to_string()) =>
+            store(FinalizedEffects::UInt(value)),
+        _ => effect
+    });
+}
+
+/// Evaluates a constant integer effect, returning None if it isn't a constant.
+fn constant_value(effect: &FinalizedEffects) -> Result<Option<u64>, ParsingError> {
+    return Ok(match effect {
+        FinalizedEffects::UInt(value) => Some(*value),
+        FinalizedEffects::HeapStore(inner) => constant_value(inner)?,
+        FinalizedEffects::MethodCall(_, method, arguments) if arguments.len() == 1 &&
+            method.data.name.starts_with("numbers::Cast") => {
+            let target = match method.return_type.as_ref().map(FinalizedTypes::name) {
+                Some(target) => target,
+                None => return Ok(None)
+            };
+            let (bits, signed) = match integer_type(&target) {
+                Some(found) => found,
+                None => return Ok(None)
+            };
+
+            // Casts wrap like they do at runtime, so only floats, which LLVM can't cast if they don't fit, are errors.
+            let value = if let Some(float) = constant_float(&arguments[0]) {
+                let truncated = float.trunc();
+                let (min, max) = if signed {
+                    (-(2f64.powi(bits as i32 - 1)), 2f64.powi(bits as i32 - 1))
+                } else {
+                    (0.0, 2f64.powi(bits as i32))
+                };
+                if truncated < min || truncated >= max || float.is_nan() {
+                    return Err(placeholder_error(format!("Constant {} doesn't fit in a {}!", float, target)));
+                }
+                // Negative numbers can't be u64 literals, so they're left to the runtime cast.
+                if truncated < 0.0 {
+                    return Ok(None);
+                }
+                truncated as u64
+            } else {
+                match constant_value(&arguments[0])? {
+                    Some(value) => value & (u64::MAX >> (64 - bits)),
+                    None => return Ok(None)
+                }
+            };
+
+            // Wrapping into a negative number also can't be a literal.
+            if value > u64::MAX >> (64 - bits + signed as u32) {
+                return Ok(None);
+            }
+            Some(value)
+        }
+        FinalizedEffects::MethodCall(_, method, arguments) if arguments.len() == 2 => {
+            let (symbol, operation): (&str, fn(u64, u64) -> Option<u64>) = if method.data.name.starts_with("math::Add<") {
+                ("+", u64::checked_add)
+            } else if method.data.name.starts_with("math::Multiply<") {
+                ("*", u64::checked_mul)
+            } else {
+                return Ok(None);
+            };
+
+            let target = match method.return_type.as_ref().map(FinalizedTypes::name) {
+                Some(target) => target,
+                None => return Ok(None)
+            };
+            let max = match integer_type(&target) {
+                Some((bits, signed)) => u64::MAX >> (64 - bits + signed as u32),
+                None => return Ok(None)
+            };
+
+            let (first, second) = match (constant_value(&arguments[0])?, constant_value(&arguments[1])?) {
+                (Some(first), Some(second)) => (first, second),
+                _ => return Ok(None)
+            };
+            match operation(first, second) {
+                Some(value) if value <= max => Some(value),
+                _ => return Err(placeholder_error(format!("Constant {} {} {} overflows a {}!", first, symbol, second, target)))
+            }
+        }
+        _ => None
+    });
+}
+
+/// Gets the value of a float literal, or None if it isn't one.
+fn constant_float(effect: &FinalizedEffects) -> Option<f64> {
+    return match effect {
+        FinalizedEffects::Float(value) => Some(*value),
+        FinalizedEffects::HeapStore(inner) => constant_float(inner),
+        _ => None
+    };
+}
+
+/// Gets the amount of bits in an integer type and if it's signed, or None if it isn't an integer type.
+fn integer_type(name: &str) -> Option<(u32, bool)> {
+    return match name {
+        "u8" => Some((8, false)),
+        "u16" => Some((16, false)),
+        "u32" => Some((32, false)),
+        "u64" => Some((64, false)),
+        "i8" => Some((8, true)),
+        "i16" => Some((16, true)),
+        "i32" => Some((32, true)),
+        "i64" => Some((64, true)),
+        _ => None
+    };
+}
+
 fn store(effect: FinalizedEffects) -> FinalizedEffects {
     return FinalizedEffects::HeapStore(Box::new(effect));
 }
@@ -597,12 +769,9 @@ pub async fn check_args(function: &Arc<CodelessFinalizedFunction>, resolver: &Bo
 pub fn assign_with_priority(operation: String, found: &Arc<StructData>, mut values: Vec<Effects>,
                             inner_operator: String, inner_data: &Arc<StructData>, mut inner_effects: Vec<Effects>,
                             inner_array: bool) -> (Option<Arc<StructData>>, Vec<Effects>) {
-    let op_priority = Attribute::find_attribute("priority", &found.attributes)
-        .map(|inner| inner.as_int_attribute().unwrap_or(0)).unwrap_or(0);
-    let op_parse_left = Attribute::find_attribute("parse_left", &found.attributes)
-        .map(|inner| inner.as_bool_attribute().unwrap_or(false)).unwrap_or(false);
-    let lhs_priority = Attribute::find_attribute("priority", &inner_data.attributes)
-        .map(|inner| inner.as_int_attribute().unwrap_or(0)).unwrap_or(0);
+    let op_priority = found.priority();
+    let op_parse_left = found.parse_left();
+    let lhs_priority = inner_data.priority();
 
     return if lhs_priority < op_priority || (!op_parse_left && lhs_priority == op_priority) {
         if inner_array {
@@ -620,4 +789,67 @@ pub fn assign_with_priority(operation: String, found: &Arc<StructData>, mut valu
         values.push(Effects::Operation(inner_operator, inner_effects));
         (Some(found.clone()), values)
     };
-}
\ No newline at end of file
+}
+
+/// Checks that the generics a structure is created with satisfy the structure's generic bounds.
+/// Ex: new Container<u64> {} errors immediately if Container's generic requires a bound u64 doesn't implement.
+async fn check_struct_bounds(target: &FinalizedTypes, syntax: &Arc<Mutex<Syntax>>) -> Result<(), ParsingError> {
+    if let FinalizedTypes::GenericType(base, generics) = target {
+        let structure = base.inner_struct();
+        if structure.generics.len() != generics.len() {
+            return Err(placeholder_error(format!("Expected {} generics for {}, found {}!",
+                                                 structure.generics.len(), structure.data.name, generics.len())));
+        }
+
+        for ((name, bounds), generic) in structure.generics.iter().zip(generics) {
+            for bound in bounds {
+                if !generic.of_type(bound, syntax.clone()).await {
+                    return Err(placeholder_error(format!("{} doesn't implement {}, required by {} of {}!",
+                                                         generic, bound, name, structure.data.name)));
+                }
+            }
+        }
+    }
+    return Ok(());
+}
+
+/// If the effect is something the user wrote, instead of control flow added by the parser.
+fn is_statement(effect: &Effects) -> bool {
+    return match effect {
+        Effects::Jump(_) | Effects::CompareJump(_, _, _) | Effects::CodeBody(_) | Effects::NOP() => false,
+        _ => true
+    };
+}
+
+/// Warns about code after a return, break, or continue which can never run.
+/// Effects don't keep their position, so the warning can't point at the code yet.
+fn warn_unreachable(after: &str, syntax: &Arc<Mutex<Syntax>>) {
+    syntax.lock().unwrap().warnings.push(placeholder_error(format!("Unreachable code after a {}!", after)));
+}
+
+/// Warns if the result of a function or type marked #[must_use] is discarded.
+/// The attribute can have a message explaining why, like #[must_use(Check the error!)].
+fn check_must_use(effect: &FinalizedEffects, syntax: &Arc<Mutex<Syntax>>) {
+    let function = match effect {
+        FinalizedEffects::MethodCall(_, function, _) | FinalizedEffects::GenericMethodCall(function, _, _) |
+        FinalizedEffects::VirtualCall(_, function, _) => function,
+        _ => return
+    };
+    let returning = match &function.return_type {
+        Some(returning) => returning,
+        None => return
+    };
+
+    let attribute = Attribute::find_attribute("must_use", &function.data.attributes).or_else(||
+        match returning {
+            FinalizedTypes::Struct(structure, _) => Attribute::find_attribute("must_use", &structure.data.attributes),
+            _ => None
+        });
+    if let Some(attribute) = attribute {
+        let mut message = format!("Unused result of {} which must be used!", function.data.name);
+        if let Some(reason) = attribute.as_string_attribute() {
+            message = format!("{} {}", message, reason);
+        }
+        syntax.lock().unwrap().warnings.push(placeholder_error(message));
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 use std::sync::Mutex;
 use syntax::function::{CodeBody, CodelessFinalizedFunction, FinalizedCodeBody, FinalizedFunction, UnfinalizedFunction};
-use syntax::{SimpleVariableManager, is_modifier, Modifier, ParsingError};
+use syntax::{Attribute, SimpleVariableManager, is_modifier, Modifier, ParsingError};
 use syntax::async_util::NameResolver;
 use syntax::code::{ExpressionType, FinalizedEffects, FinalizedExpression, FinalizedField, FinalizedMemberField};
 use syntax::syntax::Syntax;
@@ -19,6 +19,7 @@ pub async fn verify_function(mut function: UnfinalizedFunction, syntax: &Arc<Mut
             modifiers: field.modifiers,
             attributes: field.attributes,
             field: FinalizedField { field_type: field.field.field_type.finalize(syntax.clone()).await, name: field.field.name },
+            default: None,
         };
         if include_refs {
             field.field.field_type = FinalizedTypes::Reference(Box::new(field.field.field_type));
@@ -33,6 +34,18 @@ pub async fn verify_function(mut function: UnfinalizedFunction, syntax: &Arc<Mut
         None
     };
 
+    // Init functions are called before the entry point, so there's nothing to give them or return to.
+    if Attribute::find_attribute("init", &function.data.attributes).is_some() &&
+        (!fields.is_empty() || return_type.is_some() || !function.generics.is_empty()) {
+        return Err(placeholder_error(format!("Init function {} can't have arguments, generics, or a return type!",
+                                             function.data.name)));
+    }
+
+    if Attribute::find_attribute("cold", &function.data.attributes).is_some() &&
+        Attribute::find_attribute("hot", &function.data.attributes).is_some() {
+        return Err(placeholder_error(format!("Function {} can't be both cold and hot!", function.data.name)));
+    }
+
     let codeless = CodelessFinalizedFunction {
         generics: finalize_generics(syntax, function.generics).await?,
         arguments: fields,
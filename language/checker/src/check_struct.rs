@@ -1,24 +1,51 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::sync::Mutex;
-use syntax::ParsingError;
+use syntax::{ParsingError, SimpleVariableManager};
+use syntax::async_util::NameResolver;
 use syntax::code::{FinalizedField, FinalizedMemberField};
 use syntax::r#struct::{FinalizedStruct, UnfinalizedStruct};
 use syntax::syntax::Syntax;
-use syntax::types::FinalizedTypes;
+use syntax::types::{FinalizedTypes, Types};
+use crate::check_code::{placeholder_error, verify_effect};
 use crate::finalize_generics;
 use crate::output::TypesChecker;
 
-pub async fn verify_struct(_process_manager: &TypesChecker, structure: UnfinalizedStruct,
+pub async fn verify_struct(process_manager: &TypesChecker, structure: UnfinalizedStruct, resolver: Box<dyn NameResolver>,
                            syntax: &Arc<Mutex<Syntax>>, include_refs: bool) -> Result<FinalizedStruct, ParsingError> {
-    let mut finalized_fields = Vec::new();
+    let mut fields = Vec::new();
     for field in structure.fields {
-        let field = field.await?;
+        fields.push(field.await?);
+    }
+
+    // A structure that leads back to itself has an infinite layout, and finalizing its fields would wait on itself forever.
+    // Finalizing waits on every structure in the type, so references and arrays don't break the cycle either.
+    {
+        let mut locked = syntax.lock().unwrap();
+        locked.struct_fields.insert(structure.data.name.clone(),
+                                    fields.iter().map(|field| field.field.field_type.clone()).collect());
+        for field in &fields {
+            if leads_to(&field.field.field_type, &structure.data.name, &locked.struct_fields, &mut HashSet::new()) {
+                return Err(placeholder_error(format!("Recursive structure {} has infinite size because of field {}!",
+                                                     structure.data.name, field.field.name)));
+            }
+        }
+    }
+
+    let mut finalized_fields = Vec::new();
+    for field in fields {
         let mut field_type = field.field.field_type.finalize(syntax.clone()).await;
         if include_refs {
             field_type = FinalizedTypes::Reference(Box::new(field_type));
         }
+        // Defaults are checked once here with the structure's imports, and can't see any variables.
+        let default = match field.default {
+            Some(default) => Some(verify_effect(process_manager, resolver.boxed_clone(), default, &None, syntax,
+                                                &mut SimpleVariableManager { variables: HashMap::new() }, include_refs).await?),
+            None => None
+        };
         finalized_fields.push(FinalizedMemberField { modifiers: field.modifiers, attributes: field.attributes,
-            field: FinalizedField { field_type, name: field.field.name } })
+            field: FinalizedField { field_type, name: field.field.name }, default })
     }
 
     let output = FinalizedStruct {
@@ -28,4 +55,18 @@ pub async fn verify_struct(_process_manager: &TypesChecker, structure: Unfinaliz
     };
 
     return Ok(output);
-}
\ No newline at end of file
+}
+
+/// Checks if the type contains the named structure, either directly or through the fields of other structures.
+/// Structures whose fields haven't been found yet are skipped, since they'll check for the cycle themselves.
+fn leads_to(types: &Types, name: &String, struct_fields: &HashMap<String, Vec<Types>>, visited: &mut HashSet<String>) -> bool {
+    return match types {
+        Types::Struct(data) => &data.name == name || (visited.insert(data.name.clone()) &&
+            struct_fields.get(&data.name).map_or(false, |fields| fields.iter()
+                .any(|field| leads_to(field, name, struct_fields, visited)))),
+        Types::GenericType(base, bounds) => leads_to(base, name, struct_fields, visited) ||
+            bounds.iter().any(|bound| leads_to(bound, name, struct_fields, visited)),
+        Types::Generic(_, bounds) => bounds.iter().any(|bound| leads_to(bound, name, struct_fields, visited)),
+        Types::Reference(inner) | Types::Array(inner) => leads_to(inner, name, struct_fields, visited)
+    };
+}
@@ -38,7 +38,7 @@ impl ProcessManager for TypesChecker {
         return match verify_function(function, syntax, self.include_refs).await {
             Ok(output) => output,
             Err(error) => {
-                syntax.lock().unwrap().errors.push(error.clone());
+                syntax.lock().unwrap().add_error(error.clone());
                 (CodelessFinalizedFunction {
                     generics: Default::default(),
                     arguments: vec![],
@@ -54,7 +54,7 @@ impl ProcessManager for TypesChecker {
         return match verify_function_code(self, resolver, code, function, syntax, self.include_refs).await {
             Ok(output) => output,
             Err(error) => {
-                syntax.lock().unwrap().errors.push(error.clone());
+                syntax.lock().unwrap().add_error(error.clone());
                 FinalizedFunction {
                     generics: Default::default(),
                     fields: vec![],
@@ -66,13 +66,13 @@ impl ProcessManager for TypesChecker {
         }
     }
 
-    async fn verify_struct(&self, structure: UnfinalizedStruct, _resolver: Box<dyn NameResolver>, syntax: &Arc<Mutex<Syntax>>) -> FinalizedStruct {
-        match verify_struct(self, structure, &syntax, self.include_refs).await {
+    async fn verify_struct(&self, structure: UnfinalizedStruct, resolver: Box<dyn NameResolver>, syntax: &Arc<Mutex<Syntax>>) -> FinalizedStruct {
+        match verify_struct(self, structure, resolver, &syntax, self.include_refs).await {
             Ok(output) => {
                 return output
             },
             Err(error) => {
-                syntax.lock().unwrap().errors.push(error.clone());
+                syntax.lock().unwrap().add_error(error.clone());
                 FinalizedStruct {
                     generics: Default::default(),
                     fields: vec![],
@@ -1,17 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ptr;
 use std::sync::{Arc, RwLock};
 use std::sync::Mutex;
 
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::execution_engine::ExecutionEngine;
-use inkwell::module::Module;
+use inkwell::AddressSpace;
+use inkwell::module::{Linkage, Module};
 use inkwell::OptimizationLevel;
+use inkwell::passes::{PassManager, PassManagerBuilder};
+use inkwell::types::BasicType;
+use inkwell::values::{BasicValue, GlobalValue};
+use llvm_sys::support::{LLVMLoadLibraryPermanently, LLVMSearchForAddressOfSymbol};
 
-use data::CompilerArguments;
+use data::{CompilerArguments, Optimization};
 use syntax::async_util::EmptyNameResolver;
 use syntax::function::FinalizedFunction;
-use syntax::ParsingError;
+use syntax::{Attribute, ParsingError};
 use syntax::r#struct::FinalizedStruct;
 use syntax::syntax::Syntax;
 
@@ -19,11 +25,17 @@ use crate::function_compiler::{compile_block, instance_function};
 use crate::main_future::MainFuture;
 use crate::type_getter::CompilerTypeGetter;
 
+/// The highest #[init] priority. Priorities are shifted past the 0 to 100 that LLVM reserves,
+/// and object formats only support constructor priorities up to 65535.
+pub const MAX_INIT_PRIORITY: i64 = 65535 - 101;
+
 pub struct CompilerImpl<'ctx> {
     pub context: &'ctx Context,
     pub module: Module<'ctx>,
     pub builder: Builder<'ctx>,
     pub execution_engine: ExecutionEngine<'ctx>,
+    // Every string constant by its contents, so identical strings share one global
+    pub strings: Mutex<HashMap<String, GlobalValue<'ctx>>>,
 }
 
 /// SAFETY LLVM isn't safe for access across multiple threads, but this module only accesses it from
@@ -34,18 +46,38 @@ unsafe impl Send for CompilerImpl<'_> {}
 unsafe impl Sync for CompilerImpl<'_> {}
 
 impl<'ctx> CompilerImpl<'ctx> {
-    pub fn new(context: &'ctx Context) -> Self {
+    pub fn new(context: &'ctx Context, optimization: Optimization) -> Self {
         let module = context.create_module("main");
-        let execution_engine = module.create_jit_execution_engine(OptimizationLevel::None).unwrap();
+        let execution_engine = module.create_jit_execution_engine(optimization_level(optimization)).unwrap();
 
         return Self {
             module,
             context,
             builder: context.create_builder(),
             execution_engine,
+            strings: Mutex::new(HashMap::new()),
         };
     }
 
+    /// Gets the global holding the string constant, adding it if this is the first time the string is used.
+    /// The string's null terminator is already added by the parser, so it's part of the key.
+    pub fn get_string(&self, string: &String) -> GlobalValue<'ctx> {
+        let mut strings = self.strings.lock().unwrap();
+        if let Some(found) = strings.get(string) {
+            return *found;
+        }
+
+        let value = self.context.const_string(string.as_bytes(), false);
+        let global = self.module.add_global(value.get_type(), Some(AddressSpace::default()),
+                                            &format!("str.{}", strings.len()));
+        global.set_initializer(&value);
+        global.set_constant(true);
+        global.set_linkage(Linkage::Private);
+        global.set_unnamed_addr(true);
+        strings.insert(string.clone(), global);
+        return global;
+    }
+
     pub async fn compile(type_getter: &mut CompilerTypeGetter<'ctx>,
                          arguments: &CompilerArguments, syntax: &Arc<Mutex<Syntax>>,
                          functions: &Arc<RwLock<HashMap<String, Arc<FinalizedFunction>>>>,
@@ -59,6 +91,148 @@ impl<'ctx> CompilerImpl<'ctx> {
         let function = MainFuture { syntax: syntax.clone() }.await;
         instance_function(Arc::new(function.to_codeless()), type_getter);
 
+        Self::compile_queue(type_getter, functions);
+
+        //let pass_manager = PassManager::create(&self.compiler.module);
+
+        /*unsafe {
+            LLVMWriteBitcodeToFile(type_getter.compiler.module.as_mut_ptr(),
+                                   CString::new(arguments.temp_folder.join("output.bc")
+                                       .to_str().unwrap()).unwrap().as_ptr());
+        }*/
+
+        //print_formatted(type_getter.compiler.module.to_string());
+        return true;
+    }
+
+    /// Instances every function marked #[init] and adds them to the global constructors, which are
+    /// run before the entry point in order of their priority (#[init(priority)]), then their name.
+    /// Errors if an init function's priority is out of range, or if init functions call each other in a cycle.
+    pub fn compile_init(type_getter: &mut CompilerTypeGetter<'ctx>,
+                        functions: &Arc<RwLock<HashMap<String, Arc<FinalizedFunction>>>>,
+                        call_graph: &HashMap<String, Vec<String>>) -> Result<(), Vec<ParsingError>> {
+        let mut init = functions.read().unwrap().values()
+            .filter_map(|function| Attribute::find_attribute("init", &function.data.attributes)
+                .map(|attribute| (attribute.as_int_attribute().unwrap_or(0), function.clone())))
+            .collect::<Vec<_>>();
+        if init.is_empty() {
+            return Ok(());
+        }
+        init.sort_by(|(first_priority, first), (second_priority, second)|
+            first_priority.cmp(second_priority).then_with(|| first.data.name.cmp(&second.data.name)));
+
+        let mut errors = Vec::new();
+        for (priority, function) in &init {
+            if *priority < 0 || *priority > MAX_INIT_PRIORITY {
+                errors.push(compile_error(format!("Init function {} has priority {}, but it must be between 0 and {}!",
+                                                 function.data.name, priority, MAX_INIT_PRIORITY)));
+            }
+        }
+
+        let reachable = init.iter()
+            .map(|(_, function)| reachable_calls(&function.data.name, call_graph))
+            .collect::<Vec<_>>();
+        for (i, (_, first)) in init.iter().enumerate() {
+            for (j, (_, second)) in init.iter().enumerate().skip(i + 1) {
+                if reachable[i].contains(&second.data.name) && reachable[j].contains(&first.data.name) {
+                    errors.push(compile_error(format!("Init functions {} and {} depend on each other!",
+                                                     first.data.name, second.data.name)));
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let context = type_getter.compiler.context;
+        let i32_type = context.i32_type();
+        let data_type = context.i8_type().ptr_type(AddressSpace::default());
+        let mut constructors = Vec::new();
+        for (priority, function) in &init {
+            let value = type_getter.get_function(&Arc::new(function.to_codeless()));
+            constructors.push((*priority, value));
+        }
+        Self::compile_queue(type_getter, functions);
+
+        let constructor_type = context.struct_type(&[i32_type.as_basic_type_enum(),
+            constructors[0].1.get_type().ptr_type(AddressSpace::default()).as_basic_type_enum(),
+            data_type.as_basic_type_enum()], false);
+        let constructors = constructors.into_iter().map(|(priority, function)| constructor_type.const_named_struct(&[
+            // LLVM reserves priorities 0 through 100 for itself.
+            i32_type.const_int((priority + 101) as u64, false).as_basic_value_enum(),
+            function.as_global_value().as_pointer_value().as_basic_value_enum(),
+            data_type.const_null().as_basic_value_enum()])).collect::<Vec<_>>();
+        let constructors = constructor_type.const_array(constructors.as_slice());
+
+        let global = type_getter.compiler.module.add_global(constructors.get_type(), None, "llvm.global_ctors");
+        global.set_linkage(Linkage::Appending);
+        global.set_initializer(&constructors);
+        return Ok(());
+    }
+
+    /// Verifies every function in the module, returning an error naming each function LLVM rejects.
+    /// Invalid modules crash when they're run, so this turns finalizer bugs into readable errors.
+    pub fn verify(&self) -> Result<(), Vec<ParsingError>> {
+        let mut errors = Vec::new();
+        for function in self.module.get_functions() {
+            if function.count_basic_blocks() > 0 && !function.verify(false) {
+                errors.push(compile_error(format!("LLVM failed to verify function {}!",
+                                                 function.get_name().to_str().unwrap())));
+            }
+        }
+
+        if errors.is_empty() {
+            if let Err(error) = self.module.verify() {
+                errors.push(compile_error(format!("LLVM failed to verify module: {}", error.to_string())));
+            }
+        }
+
+        return if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        };
+    }
+
+    /// Checks that every function declared but not defined in the module can be found in the process's symbols,
+    /// so calling a missing external function is an error instead of a crash in the JIT.
+    pub fn resolve_externals(&self) -> Result<(), Vec<ParsingError>> {
+        // Loading a null library makes the process's own symbols (like libc) searchable.
+        unsafe { LLVMLoadLibraryPermanently(ptr::null()) };
+        let mut errors = Vec::new();
+        for function in self.module.get_functions() {
+            let name = function.get_name();
+            if function.count_basic_blocks() > 0 || name.to_bytes().starts_with(b"llvm.") {
+                continue;
+            }
+            if unsafe { LLVMSearchForAddressOfSymbol(name.as_ptr()) }.is_null() {
+                errors.push(compile_error(format!("Failed to find external function {}!", name.to_str().unwrap())));
+            }
+        }
+
+        return if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        };
+    }
+
+    /// Runs LLVM's optimization passes for the given level over the whole module.
+    pub fn optimize(&self, optimization: Optimization) {
+        if optimization == Optimization::None {
+            return;
+        }
+
+        let builder = PassManagerBuilder::create();
+        builder.set_optimization_level(optimization_level(optimization));
+        let pass_manager = PassManager::create(());
+        builder.populate_module_pass_manager(&pass_manager);
+        pass_manager.run_on(&self.module);
+    }
+
+    /// Compiles every function waiting to be compiled.
+    fn compile_queue(type_getter: &mut CompilerTypeGetter<'ctx>,
+                     functions: &Arc<RwLock<HashMap<String, Arc<FinalizedFunction>>>>) {
         while !type_getter.compiling.is_empty() {
             let (function_type, function) = unsafe {
                 Arc::get_mut_unchecked(&mut type_getter.compiling)
@@ -88,16 +262,57 @@ impl<'ctx> CompilerImpl<'ctx> {
             compile_block(&finalized_function.code, function_type,
                           &mut type_getter.for_function(&finalized_function, function_type), &mut 0);
         }
+    }
+}
+
+/// Converts the optimization setting to LLVM's version.
+fn optimization_level(optimization: Optimization) -> OptimizationLevel {
+    return match optimization {
+        Optimization::None => OptimizationLevel::None,
+        Optimization::Less => OptimizationLevel::Less,
+        Optimization::Default => OptimizationLevel::Default,
+        Optimization::Aggressive => OptimizationLevel::Aggressive
+    };
+}
+
+/// Every function reachable by calls from the function, not including itself unless it's recursive.
+fn reachable_calls(function: &String, call_graph: &HashMap<String, Vec<String>>) -> HashSet<String> {
+    let mut found = HashSet::new();
+    let mut checking = vec!(function.clone());
+    while let Some(checking_function) = checking.pop() {
+        if let Some(calls) = call_graph.get(&checking_function) {
+            for call in calls {
+                if found.insert(call.clone()) {
+                    checking.push(call.clone());
+                }
+            }
+        }
+    }
+    return found;
+}
 
-        //let pass_manager = PassManager::create(&self.compiler.module);
+/// An error from compiling, like failing to verify, which doesn't come from any file so it has no position.
+pub fn compile_error(message: String) -> ParsingError {
+    return ParsingError::new(String::new(), (0, 0), 0, (0, 0), 0, message);
+}
 
-        /*unsafe {
-            LLVMWriteBitcodeToFile(type_getter.compiler.module.as_mut_ptr(),
-                                   CString::new(arguments.temp_folder.join("output.bc")
-                                       .to_str().unwrap()).unwrap().as_ptr());
-        }*/
+#[cfg(test)]
+mod test {
+    use inkwell::context::Context;
+    use data::Optimization;
+    use crate::compiler::CompilerImpl;
 
-        //print_formatted(type_getter.compiler.module.to_string());
-        return true;
+    #[test]
+    pub fn test_verify_error() {
+        let context = Context::create();
+        let compiler = CompilerImpl::new(&context, Optimization::None);
+        // A block without a terminator, like the finalizer would make if it forgot a jump.
+        let function = compiler.module.add_function("broken", context.void_type().fn_type(&[], false), None);
+        compiler.builder.position_at_end(context.append_basic_block(function, "0"));
+        compiler.builder.build_alloca(context.i64_type(), "1");
+
+        let errors = compiler.verify().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "LLVM failed to verify function broken!");
     }
-}
\ No newline at end of file
+}
@@ -5,7 +5,7 @@ use inkwell::AddressSpace;
 use inkwell::basic_block::BasicBlock;
 use inkwell::module::Linkage;
 
-use inkwell::values::{BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallableValue, FunctionValue};
+use inkwell::values::{BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallableValue, FunctionValue, IntValue};
 use inkwell::types::{BasicType, BasicTypeEnum};
 
 use syntax::{Attribute, is_modifier, Modifier};
@@ -16,7 +16,7 @@ use syntax::types::FinalizedTypes;
 use crate::internal::instructions::{compile_internal, malloc_type};
 use crate::internal::intrinsics::compile_llvm_intrinsics;
 use crate::type_getter::CompilerTypeGetter;
-use crate::util::create_function_value;
+use crate::util::{compile_extern, create_function_value};
 
 pub fn instance_function<'a, 'ctx>(function: Arc<CodelessFinalizedFunction>, type_getter: &mut CompilerTypeGetter<'ctx>) -> FunctionValue<'ctx> {
     let value;
@@ -28,9 +28,15 @@ pub fn instance_function<'a, 'ctx>(function: Arc<CodelessFinalizedFunction>, typ
         value = compile_llvm_intrinsics(function.data.name.split("::").last().unwrap(), type_getter);
     } else if is_modifier(function.data.modifiers, Modifier::Internal) {
         value = create_function_value(&function, type_getter, None);
-        compile_internal(&type_getter, &type_getter.compiler, &function.data.name, value);
+        compile_internal(&type_getter, &type_getter.compiler, &function, value);
     } else if is_modifier(function.data.modifiers, Modifier::Extern) {
-        value = create_function_value(&function, type_getter, Some(Linkage::External))
+        value = create_function_value(&function, type_getter, None);
+        compile_extern(&function, type_getter, value);
+    } else if function.data.name.contains('$') {
+        // Degenericed functions (which have a $ in their name) can be instanced by multiple modules,
+        // so let the linker merge the duplicates.
+        value = create_function_value(&function, type_getter, Some(Linkage::LinkOnceODR));
+        unsafe { Arc::get_mut_unchecked(&mut type_getter.compiling) }.push((value, function));
     } else {
         value = create_function_value(&function, type_getter, None);
         unsafe { Arc::get_mut_unchecked(&mut type_getter.compiling) }.push((value, function));
@@ -106,6 +112,11 @@ pub fn compile_block<'ctx>(code: &FinalizedCodeBody, function: FunctionValue<'ct
                 } else {
                     match &line.effect {
                         FinalizedEffects::CodeBody(body) => {
+                            // Simple ifs that only pick between two constants don't need any branches.
+                            if compile_select(body, function, type_getter, id) {
+                                continue;
+                            }
+
                             let destination =
                                 unwrap_or_create(&body.label, function, type_getter);
                             type_getter.compiler.builder.build_unconditional_branch(
@@ -178,6 +189,9 @@ pub fn compile_effect<'ctx>(type_getter: &mut CompilerTypeGetter<'ctx>, function
         }
         //Calling function, function arguments
         FinalizedEffects::MethodCall(pointer, calling_function, arguments) => {
+            if let Some(found) = compile_short_circuit(type_getter, function, calling_function, arguments, id) {
+                return Some(found);
+            }
             let mut final_arguments = Vec::new();
 
             let calling = type_getter.get_function(calling_function);
@@ -278,7 +292,7 @@ pub fn compile_effect<'ctx>(type_getter: &mut CompilerTypeGetter<'ctx>, function
         FinalizedEffects::Float(float) => Some(type_getter.compiler.context.f64_type().const_float(*float).as_basic_value_enum()),
         FinalizedEffects::UInt(int) => Some(type_getter.compiler.context.i64_type().const_int(*int, false).as_basic_value_enum()),
         FinalizedEffects::Bool(bool) => Some(type_getter.compiler.context.bool_type().const_int(*bool as u64, false).as_basic_value_enum()),
-        FinalizedEffects::String(string) => Some(type_getter.compiler.context.const_string(string.as_bytes(), false).as_basic_value_enum()),
+        FinalizedEffects::String(string) => Some(type_getter.compiler.get_string(string).as_pointer_value().as_basic_value_enum()),
         FinalizedEffects::Char(char) => Some(type_getter.compiler.context.i8_type().const_int(*char as u64, false).as_basic_value_enum()),
         FinalizedEffects::HeapStore(inner) => {
             let mut output = compile_effect(type_getter, function, inner, id).unwrap();
@@ -460,6 +474,97 @@ pub fn compile_effect<'ctx>(type_getter: &mut CompilerTypeGetter<'ctx>, function
     };
 }
 
+/// Lowers an if/else which sets the same variable to a constant in both branches into a select instead of branches.
+/// Returns false if the body doesn't match that pattern, in which case nothing is compiled.
+fn compile_select<'ctx>(body: &FinalizedCodeBody, function: FunctionValue<'ctx>, type_getter: &mut CompilerTypeGetter<'ctx>,
+                        id: &mut u64) -> bool {
+    if body.expressions.len() != 3 {
+        return false;
+    }
+
+    let (comparing, then_label, else_label) = match &body.expressions[0].effect {
+        FinalizedEffects::CompareJump(comparing, then_label, else_label) => (comparing, then_label, else_label),
+        _ => return false
+    };
+
+    let end = body.label.clone() + "end";
+    let (then_variable, then_value) = match select_arm(&body.expressions[1].effect, then_label, &end) {
+        Some(found) => found,
+        None => return false
+    };
+    let (else_variable, else_value) = match select_arm(&body.expressions[2].effect, else_label, &end) {
+        Some(found) => found,
+        None => return false
+    };
+
+    if then_variable != else_variable {
+        return false;
+    }
+
+    // Constants don't build any instructions, so they're safe to compile before checking the types.
+    let then_value = compile_effect(type_getter, function, then_value, id).unwrap();
+    let else_value = compile_effect(type_getter, function, else_value, id).unwrap();
+    if then_value.get_type() != else_value.get_type() {
+        return false;
+    }
+
+    let comparing = compile_effect(type_getter, function, comparing, id).unwrap();
+    let comparing = if comparing.is_pointer_value() {
+        *id += 1;
+        type_getter.compiler.builder.build_load(comparing.into_pointer_value(), &(*id - 1).to_string()).into_int_value()
+    } else {
+        comparing.into_int_value()
+    };
+
+    let selected = type_getter.compiler.builder.build_select(comparing, then_value, else_value, &id.to_string());
+    *id += 1;
+    let pointer = type_getter.variables.get(then_variable).unwrap().1.into_pointer_value();
+    type_getter.compiler.builder.build_store(pointer, selected);
+    return true;
+}
+
+/// Gets the variable and constant of an if branch which only sets a variable to a constant then jumps to the end.
+fn select_arm<'a>(effect: &'a FinalizedEffects, label: &String, end: &String) -> Option<(&'a String, &'a FinalizedEffects)> {
+    let body = match effect {
+        FinalizedEffects::CodeBody(body) if &body.label == label => body,
+        _ => return None
+    };
+
+    if body.expressions.len() < 2 || body.expressions.iter().any(|expression| expression.expression_type != ExpressionType::Line) {
+        return None;
+    }
+
+    // Anything after the first jump is unreachable, so only the first one has to go to the end.
+    match &body.expressions[1].effect {
+        FinalizedEffects::Jump(jumping) if jumping == end => {}
+        _ => return None
+    }
+    if !body.expressions[2..].iter().all(|expression| matches!(expression.effect, FinalizedEffects::Jump(_))) {
+        return None;
+    }
+
+    let (setting, value) = match &body.expressions[0].effect {
+        FinalizedEffects::Set(setting, value) => (setting, value),
+        _ => return None
+    };
+
+    let name = match setting.as_ref() {
+        FinalizedEffects::LoadVariable(name) => name,
+        _ => return None
+    };
+
+    let value = match value.as_ref() {
+        FinalizedEffects::HeapStore(inner) => inner.as_ref(),
+        other => other
+    };
+
+    return match value {
+        FinalizedEffects::UInt(_) | FinalizedEffects::Float(_) |
+        FinalizedEffects::Bool(_) | FinalizedEffects::Char(_) => Some((name, value)),
+        _ => None
+    };
+}
+
 fn store_and_load<'ctx, T: BasicType<'ctx>>(type_getter: &mut CompilerTypeGetter<'ctx>, types: T, inputer: BasicValueEnum<'ctx>, id: &mut u64) -> Option<BasicValueEnum<'ctx>> {
     let pointer = type_getter.compiler.builder.build_alloca(types, &id.to_string());
     *id += 1;
@@ -467,6 +572,60 @@ fn store_and_load<'ctx, T: BasicType<'ctx>>(type_getter: &mut CompilerTypeGetter
     return Some(pointer.as_basic_value_enum());
 }
 
+/// Compiles && and || on booleans with branches, so the second operand is only evaluated if it can change the result.
+/// Returns None for any other method call.
+fn compile_short_circuit<'ctx>(type_getter: &mut CompilerTypeGetter<'ctx>, function: FunctionValue<'ctx>,
+                               calling_function: &Arc<CodelessFinalizedFunction>, arguments: &Vec<FinalizedEffects>,
+                               id: &mut u64) -> Option<BasicValueEnum<'ctx>> {
+    let and = if calling_function.data.name.starts_with("math::And<") {
+        true
+    } else if calling_function.data.name.starts_with("math::Or<") {
+        false
+    } else {
+        return None;
+    };
+    if arguments.iter().any(|argument| argument.get_return(type_getter)
+        .and_then(|types| types.name_safe()) != Some("bool".to_string())) {
+        return None;
+    }
+
+    let result = malloc_type(type_getter,
+                             type_getter.compiler.context.bool_type().ptr_type(AddressSpace::default()).const_zero(), id);
+    let first = load_bool(type_getter, function, &arguments[0], id);
+    type_getter.compiler.builder.build_store(result, first);
+
+    *id += 1;
+    let second_block = type_getter.compiler.context.append_basic_block(function, &format!("{}second", id));
+    let end_block = type_getter.compiler.context.append_basic_block(function, &format!("{}end", id));
+    if and {
+        type_getter.compiler.builder.build_conditional_branch(first, second_block, end_block);
+    } else {
+        type_getter.compiler.builder.build_conditional_branch(first, end_block, second_block);
+    }
+
+    type_getter.compiler.builder.position_at_end(second_block);
+    type_getter.current_block = Some(second_block);
+    let second = load_bool(type_getter, function, &arguments[1], id);
+    type_getter.compiler.builder.build_store(result, second);
+    type_getter.compiler.builder.build_unconditional_branch(end_block);
+
+    type_getter.compiler.builder.position_at_end(end_block);
+    type_getter.current_block = Some(end_block);
+    return Some(result.as_basic_value_enum());
+}
+
+/// Compiles the effect and loads the boolean it points to.
+fn load_bool<'ctx>(type_getter: &mut CompilerTypeGetter<'ctx>, function: FunctionValue<'ctx>,
+                   effect: &FinalizedEffects, id: &mut u64) -> IntValue<'ctx> {
+    let value = compile_effect(type_getter, function, effect, id).unwrap();
+    return if value.is_pointer_value() {
+        *id += 1;
+        type_getter.compiler.builder.build_load(value.into_pointer_value(), &(*id - 1).to_string()).into_int_value()
+    } else {
+        value.into_int_value()
+    };
+}
+
 fn add_args<'ctx, 'a>(final_arguments: &'a mut Vec<BasicMetadataValueEnum<'ctx>>, type_getter: &mut CompilerTypeGetter<'ctx>,
                       function: FunctionValue<'ctx>, arguments: &'a Vec<FinalizedEffects>, offset: bool, id: &mut u64) {
     for i in offset as usize..arguments.len() {
@@ -1,17 +1,22 @@
+use std::sync::Arc;
 use inkwell::builder::Builder;
 use inkwell::{AddressSpace, IntPredicate};
 use inkwell::types::{BasicType, BasicTypeEnum};
 use inkwell::values::{BasicMetadataValueEnum, BasicValue, BasicValueEnum, FunctionValue, PointerValue};
+use syntax::function::CodelessFinalizedFunction;
+use syntax::types::FinalizedTypes;
 use crate::compiler::CompilerImpl;
 use crate::internal::intrinsics::compile_llvm_intrinsics;
 use crate::type_getter::CompilerTypeGetter;
 
-pub fn compile_internal<'ctx>(type_getter: &CompilerTypeGetter<'ctx>, compiler: &CompilerImpl<'ctx>, name: &String, value: FunctionValue<'ctx>) {
+pub fn compile_internal<'ctx>(type_getter: &CompilerTypeGetter<'ctx>, compiler: &CompilerImpl<'ctx>,
+                             function: &Arc<CodelessFinalizedFunction>, value: FunctionValue<'ctx>) {
+    let name = &function.data.name;
     let block = compiler.context.append_basic_block(value, "0");
     compiler.builder.position_at_end(block);
     let params = value.get_params();
     if name.starts_with("numbers::Cast") {
-        build_cast(value.get_params().get(0).unwrap(), value.get_type().get_return_type().unwrap(), compiler);
+        build_cast(function, value.get_params().get(0).unwrap(), value.get_type().get_return_type().unwrap(), compiler);
     } else if name.starts_with("string::Cast") {
         type_getter.compiler.builder.build_return(Some(value.get_params().get(0).unwrap()));
     } else if name.starts_with("string::Add<char + u64>_char::add") {
@@ -71,7 +76,14 @@ pub fn compile_internal<'ctx>(type_getter: &CompilerTypeGetter<'ctx>, compiler:
                                                                                                  &[plus_one], "10") },
                                                  type_getter.compiler.context.i8_type().const_zero());
         type_getter.compiler.builder.build_return(Some(&malloc.as_basic_value_enum()));
-    } else if name.starts_with("math::Add") {
+    } else if name.starts_with("reflect::field_names") {
+        // The function is degenericed, so the argument's type is the structure being reflected.
+        let names = function.arguments[0].field.field_type.inner_struct().reflect_fields(false).iter()
+            .map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(",") + "\0";
+        let string = compiler.get_string(&names).as_pointer_value();
+        compiler.builder.build_return(Some(&compiler.builder.build_bitcast(string,
+                                                                           value.get_type().get_return_type().unwrap(), "1")));
+    } else if name.starts_with("math::Add") || name.starts_with("math::WrappingAdd") {
         let pointer_type = params.get(0).unwrap().into_pointer_value();
         let malloc = malloc_type(type_getter, pointer_type.get_type().const_zero(), &mut 0);
 
@@ -79,20 +91,35 @@ pub fn compile_internal<'ctx>(type_getter: &CompilerTypeGetter<'ctx>, compiler:
                                                        compiler.builder.build_load(params.get(1).unwrap().into_pointer_value(), "3").into_int_value(), "1");
         compiler.builder.build_store(malloc, returning);
         compiler.builder.build_return(Some(&malloc));
-    } else if name.starts_with("math::Subtract") {
+    } else if name.starts_with("math::Subtract") || name.starts_with("math::WrappingSubtract") {
         let pointer_type = params.get(0).unwrap().into_pointer_value();
         let malloc = malloc_type(type_getter, pointer_type.get_type().const_zero(), &mut 0);
         let returning = compiler.builder.build_int_sub(compiler.builder.build_load(params.get(0).unwrap().into_pointer_value(), "2").into_int_value(),
                                                        compiler.builder.build_load(params.get(1).unwrap().into_pointer_value(), "3").into_int_value(), "1");
         compiler.builder.build_store(malloc, returning);
         compiler.builder.build_return(Some(&malloc));
-    } else if name.starts_with("math::Multiply") {
+    } else if name.starts_with("math::Multiply") || name.starts_with("math::WrappingMultiply") {
         let pointer_type = params.get(0).unwrap().into_pointer_value();
         let malloc = malloc_type(type_getter, pointer_type.get_type().const_zero(), &mut 0);
         let returning = compiler.builder.build_int_mul(compiler.builder.build_load(params.get(0).unwrap().into_pointer_value(), "2").into_int_value(),
                                                        compiler.builder.build_load(params.get(1).unwrap().into_pointer_value(), "3").into_int_value(), "1");
         compiler.builder.build_store(malloc, returning);
         compiler.builder.build_return(Some(&malloc));
+    } else if name.starts_with("math::SaturatingAdd") || name.starts_with("math::SaturatingSubtract") {
+        let pointer_type = params.get(0).unwrap().into_pointer_value();
+        let malloc = malloc_type(type_getter, pointer_type.get_type().const_zero(), &mut 0);
+        let first = compiler.builder.build_load(pointer_type, "2").into_int_value();
+        let second = compiler.builder.build_load(params.get(1).unwrap().into_pointer_value(), "3").into_int_value();
+        // LLVM's saturating intrinsics are named by signedness and bit width, for example llvm.uadd.sat.i64
+        let intrinsic = format!("llvm.{}{}.sat.i{}", if is_unsigned(name) { "u" } else { "s" },
+                                if name.starts_with("math::SaturatingAdd") { "add" } else { "sub" },
+                                first.get_type().get_bit_width());
+        let intrinsic = compiler.module.get_function(&intrinsic).unwrap_or_else(|| compiler.module.add_function(&intrinsic,
+            first.get_type().fn_type(&[first.get_type().into(), first.get_type().into()], false), None));
+        let returning = compiler.builder.build_call(intrinsic, &[first.into(), second.into()], "1")
+            .try_as_basic_value().unwrap_left();
+        compiler.builder.build_store(malloc, returning);
+        compiler.builder.build_return(Some(&malloc));
     } else if name.starts_with("math::Divide") {
         let pointer_type = params.get(0).unwrap().into_pointer_value();
         let malloc = malloc_type(type_getter, pointer_type.get_type().const_zero(), &mut 0);
@@ -146,6 +173,26 @@ pub fn compile_internal<'ctx>(type_getter: &CompilerTypeGetter<'ctx>, compiler:
 
         let gep = compiler.builder.build_load(gep, "2");
         compiler.builder.build_return(Some(&gep));
+    } else if name.starts_with("array::Array") && name.ends_with("::length") {
+        // The length is stored before the first element.
+        let length = compiler.builder.build_bitcast(params.get(0).unwrap().into_pointer_value(),
+                                                    compiler.context.i64_type().ptr_type(AddressSpace::default()), "1").into_pointer_value();
+        let malloc = malloc_type(type_getter, compiler.context.i64_type().ptr_type(AddressSpace::default()).const_zero(), &mut 0);
+        compiler.builder.build_store(malloc, compiler.builder.build_load(length, "2"));
+        compiler.builder.build_return(Some(&malloc));
+    } else if name.starts_with("array::SetIndex") {
+        let offset = get_loaded(&compiler.builder, params.get(1).unwrap()).into_int_value();
+        let offset = compiler.builder.build_int_add(offset, compiler.context.i64_type().const_int(1, false), "3");
+
+        let gep;
+        unsafe {
+            gep = compiler.builder
+                .build_in_bounds_gep(params.get(0).unwrap().into_pointer_value(),
+                                     &[offset], "1");
+        }
+
+        compiler.builder.build_store(gep, *params.get(2).unwrap());
+        compiler.builder.build_return(None);
     } else if name.starts_with("array::Empty") {
         let size = unsafe {
             type_getter.compiler.builder.build_gep(value.get_type().get_return_type().unwrap()
@@ -168,6 +215,17 @@ pub fn compile_internal<'ctx>(type_getter: &CompilerTypeGetter<'ctx>, compiler:
             .build_not(compiler.builder.build_load(params.get(0).unwrap().into_pointer_value(), "1").into_int_value(), "0");
         compiler.builder.build_store(malloc, returning);
         compiler.builder.build_return(Some(&malloc));
+    } else if name.starts_with("math::Negate") {
+        let pointer_type = params.get(0).unwrap().into_pointer_value();
+        let malloc = malloc_type(type_getter, pointer_type.get_type().const_zero(), &mut 0);
+        let value = compiler.builder.build_load(pointer_type, "1");
+        let returning = if value.is_float_value() {
+            compiler.builder.build_float_neg(value.into_float_value(), "0").as_basic_value_enum()
+        } else {
+            compiler.builder.build_int_neg(value.into_int_value(), "0").as_basic_value_enum()
+        };
+        compiler.builder.build_store(malloc, returning);
+        compiler.builder.build_return(Some(&malloc));
     } else if name.starts_with("math::BitXOR") {
         let pointer_type = params.get(0).unwrap().into_pointer_value();
         let malloc = malloc_type(type_getter, pointer_type.get_type().const_zero(), &mut 0);
@@ -258,11 +316,47 @@ fn get_loaded<'ctx>(compiler: &Builder<'ctx>, value: &BasicValueEnum<'ctx>) -> B
     return value.clone();
 }
 
-fn build_cast(first: &BasicValueEnum, _second: BasicTypeEnum, compiler: &CompilerImpl) {
-    //TODO float casting
-    compiler.builder.build_return(Some(&compiler.builder.build_load(first.into_pointer_value(), "1")));
+/// Casts between number types. Integers wrap when narrowing, and floats are truncated towards zero.
+fn build_cast<'ctx>(function: &Arc<CodelessFinalizedFunction>, first: &BasicValueEnum<'ctx>, second: BasicTypeEnum<'ctx>, compiler: &CompilerImpl<'ctx>) {
+    let loaded = compiler.builder.build_load(first.into_pointer_value(), "1");
+    // The function is degenericed, so the argument and return types are the source and target types.
+    let unsigned = is_unsigned_type(&function.arguments[0].field.field_type);
+    let unsigned_target = function.return_type.as_ref().map(is_unsigned_type).unwrap_or(false);
+    let returning = match (loaded, second) {
+        (BasicValueEnum::IntValue(int), BasicTypeEnum::IntType(target)) => {
+            let (from, to) = (int.get_type().get_bit_width(), target.get_bit_width());
+            if from > to {
+                compiler.builder.build_int_truncate(int, target, "2").as_basic_value_enum()
+            } else if from == to {
+                int.as_basic_value_enum()
+            } else if unsigned {
+                compiler.builder.build_int_z_extend(int, target, "2").as_basic_value_enum()
+            } else {
+                compiler.builder.build_int_s_extend(int, target, "2").as_basic_value_enum()
+            }
+        }
+        (BasicValueEnum::IntValue(int), BasicTypeEnum::FloatType(target)) => if unsigned {
+            compiler.builder.build_unsigned_int_to_float(int, target, "2").as_basic_value_enum()
+        } else {
+            compiler.builder.build_signed_int_to_float(int, target, "2").as_basic_value_enum()
+        },
+        (BasicValueEnum::FloatValue(float), BasicTypeEnum::IntType(target)) => if unsigned_target {
+            compiler.builder.build_float_to_unsigned_int(float, target, "2").as_basic_value_enum()
+        } else {
+            compiler.builder.build_float_to_signed_int(float, target, "2").as_basic_value_enum()
+        },
+        (BasicValueEnum::FloatValue(float), BasicTypeEnum::FloatType(target)) =>
+            compiler.builder.build_float_cast(float, target, "2").as_basic_value_enum(),
+        (loaded, _) => loaded
+    };
+    compiler.builder.build_return(Some(&returning));
 }
 
+/// Checks if the number type is unsigned. Booleans are unsigned, so true is 1 instead of -1.
+fn is_unsigned_type(types: &FinalizedTypes) -> bool {
+    let name = types.name();
+    return is_unsigned(&name) || name == "bool";
+}
 
 fn is_unsigned(name: &String) -> bool {
     if name.ends_with("u64") || name.ends_with("u32") || name.ends_with("u16") || name.ends_with("u8") {
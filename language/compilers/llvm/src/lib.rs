@@ -1,18 +1,23 @@
 #![feature(get_mut_unchecked, box_into_inner)]
 
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
 use std::sync::Mutex;
+use std::time::Instant;
 
 use inkwell::context::Context;
+use inkwell::OptimizationLevel;
+use inkwell::targets::{CodeModel, FileType, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple};
 use tokio::sync::mpsc::Receiver;
 use async_trait::async_trait;
 use data::CompilerArguments;
+use syntax::ParsingError;
 use syntax::function::FinalizedFunction;
 use syntax::r#struct::FinalizedStruct;
 use syntax::syntax::{Compiler, Syntax};
 
-use crate::compiler::CompilerImpl;
+use crate::compiler::{compile_error, CompilerImpl};
 use crate::type_getter::CompilerTypeGetter;
 
 pub mod internal;
@@ -48,23 +53,111 @@ impl LLVMCompiler {
             context: Context::create(),
         };
     }
+
+    /// Compiles the target like compile, but writes it to an object file instead of running it.
+    /// The host's target triple is used if none is given.
+    pub async fn compile_object(&self, mut receiver: Receiver<()>, syntax: &Arc<Mutex<Syntax>>,
+                                triple: Option<&str>, out: &Path) -> Result<(), Vec<ParsingError>> {
+        let binding = self.build_or_errors(&mut receiver, syntax).await?;
+
+        Target::initialize_all(&InitializationConfig::default());
+        let triple = match triple {
+            Some(triple) => TargetTriple::create(triple),
+            None => TargetMachine::get_default_triple()
+        };
+        let target = Target::from_triple(&triple)
+            .map_err(|error| vec!(compile_error(format!("Unknown target {}: {}",
+                                                        triple.as_str().to_string_lossy(), error))))?;
+        let machine = target.create_target_machine(&triple, "generic", "", OptimizationLevel::Default,
+                                                   RelocMode::PIC, CodeModel::Default)
+            .ok_or_else(|| vec!(compile_error(format!("Failed to create a target machine for {}",
+                                                      triple.as_str().to_string_lossy()))))?;
+
+        // The module was made for the JIT, so it has to be switched to the new target.
+        let module = &binding.compiler.module;
+        module.set_triple(&triple);
+        module.set_data_layout(&machine.get_target_data().get_data_layout());
+        return machine.write_to_file(module, FileType::Object, out)
+            .map_err(|error| vec!(compile_error(format!("Failed to write {}: {}", out.display(), error))));
+    }
+
+    /// Builds the target, returning the syntax's errors if it fails.
+    async fn build_or_errors(&self, receiver: &mut Receiver<()>, syntax: &Arc<Mutex<Syntax>>)
+                             -> Result<CompilerTypeGetter<'_>, Vec<ParsingError>> {
+        if let Some(binding) = self.build(receiver, syntax).await {
+            return Ok(binding);
+        }
+
+        let errors = syntax.lock().unwrap().errors.clone();
+        return Err(if errors.is_empty() {
+            vec!(compile_error(format!("Failed to find target {}!", self.arguments.target)))
+        } else {
+            errors
+        });
+    }
+
+    /// Compiles the target and everything it uses. Returns None if the target couldn't be found
+    /// or if the compiled code failed to verify, in which case the errors are added to the syntax.
+    /// The target must not take any arguments, since nothing could be passed to them.
+    async fn build(&self, receiver: &mut Receiver<()>, syntax: &Arc<Mutex<Syntax>>) -> Option<CompilerTypeGetter<'_>> {
+        // Waits until everything is verified, so the codegen timing doesn't include waiting on the checker.
+        receiver.recv().await?;
+        let start = Instant::now();
+        let mut binding = CompilerTypeGetter::new(
+            Arc::new(CompilerImpl::new(&self.context, self.arguments.optimization)), syntax.clone());
+
+        if !CompilerImpl::compile(&mut binding, &self.arguments,
+                                  syntax, &self.compiling, &self.struct_compiling).await {
+            syntax.lock().unwrap().errors.push(compile_error(format!("Failed to find target {}!", self.arguments.target)));
+            return None;
+        }
+
+        let arguments = self.compiling.read().unwrap().get(&self.arguments.target)
+            .map_or(0, |target| target.fields.len());
+        if arguments != 0 {
+            syntax.lock().unwrap().errors.push(compile_error(
+                format!("Target {} can't take any arguments, but it takes {}!", self.arguments.target, arguments)));
+            return None;
+        }
+        // Every function is finalized by now, so all the init functions can be found.
+        let call_graph = syntax.lock().unwrap().call_graph();
+        if let Err(errors) = CompilerImpl::compile_init(&mut binding, &self.compiling, &call_graph) {
+            syntax.lock().unwrap().errors.extend(errors);
+            return None;
+        }
+        if self.arguments.verify {
+            if let Err(errors) = binding.compiler.verify() {
+                syntax.lock().unwrap().errors.extend(errors);
+                return None;
+            }
+        }
+        // Optimizing invalid code can crash LLVM, so this has to be after verifying.
+        binding.compiler.optimize(self.arguments.optimization);
+        syntax.lock().unwrap().record_timing("codegen", start);
+        return Some(binding);
+    }
 }
 
 #[async_trait]
 impl<T> Compiler<T> for LLVMCompiler {
+    /// Runs the target, returning what it returned. Targets can return a value like an i64 exit code.
+    /// If the target is missing or fails to compile, None is returned and the errors are added to the syntax,
+    /// so a target that returns nothing can be told apart by the syntax having no errors.
     async fn compile(&self, mut receiver: Receiver<()>, syntax: &Arc<Mutex<Syntax>>) -> Option<T> {
-        let mut binding = CompilerTypeGetter::new(
-            Arc::new(CompilerImpl::new(&self.context)), syntax.clone());
-
-        if CompilerImpl::compile(&mut binding, &self.arguments,
-                                 syntax, &self.compiling, &self.struct_compiling).await {
-            if let Some(_) = receiver.recv().await {
-                return binding.get_target(&self.arguments.target).map(|inner| unsafe { inner.call() });
-            }
-        } else {
-            receiver.recv().await;
+        let binding = self.build(&mut receiver, syntax).await?;
+        // Object files are resolved by the linker, but the JIT has to find every external function itself.
+        if let Err(errors) = binding.compiler.resolve_externals() {
+            syntax.lock().unwrap().errors.extend(errors);
+            return None;
         }
+        binding.compiler.execution_engine.run_static_constructors();
+        return binding.get_target(&self.arguments.target).map(|inner| unsafe { inner.call() });
+    }
 
-        return None;
+    /// Returns the module's LLVM IR instead of running it.
+    /// No user code is run, so this can be used to look at miscompiled code.
+    async fn emit_ir(&self, mut receiver: Receiver<()>, syntax: &Arc<Mutex<Syntax>>) -> Result<String, Vec<ParsingError>> {
+        let binding = self.build_or_errors(&mut receiver, syntax).await?;
+        return Ok(binding.compiler.module.print_to_string().to_string());
     }
-}
\ No newline at end of file
+}
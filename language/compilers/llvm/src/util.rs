@@ -1,10 +1,15 @@
 use std::ops::Deref;
 use std::sync::Arc;
+use inkwell::attributes::AttributeLoc;
 use inkwell::module::Linkage;
-use inkwell::types::BasicType;
-use inkwell::values::FunctionValue;
+use inkwell::AddressSpace;
+use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum};
+use inkwell::values::{BasicMetadataValueEnum, FunctionValue};
+use syntax::Attribute;
 use syntax::function::CodelessFinalizedFunction;
 use syntax::types::FinalizedTypes;
+use crate::internal::instructions::malloc_type;
+use crate::internal::structs::get_internal_struct;
 use crate::type_getter::CompilerTypeGetter;
 
 pub fn print_formatted(input: String) {
@@ -57,5 +62,89 @@ pub fn create_function_value<'ctx>(function: &Arc<CodelessFinalizedFunction>, ty
         None => type_getter.compiler.context.void_type().fn_type(params.as_slice(), false)
     };
 
-    return type_getter.compiler.module.add_function(&function.data.name, llvm_function, linkage);
-}
\ No newline at end of file
+    let value = type_getter.compiler.module.add_function(&function.data.name, llvm_function, linkage);
+    // Pass along #[cold] and #[hot] to LLVM as hints for block placement and inlining.
+    for hint in ["cold", "hot"] {
+        if Attribute::find_attribute(hint, &function.data.attributes).is_some() {
+            let kind = inkwell::attributes::Attribute::get_named_enum_kind_id(hint);
+            value.add_attribute(AttributeLoc::Function, type_getter.compiler.context.create_enum_attribute(kind, 0));
+        }
+    }
+    return value;
+}
+
+/// Compiles the body of an external function, which calls the C function of the same unqualified name.
+/// Raven passes numbers by pointer, but C passes them by value, so the arguments are loaded before the call
+/// and the returned value is stored like any other Raven return.
+/// Anything that isn't a number is passed as a pointer, structures aren't passed by value yet.
+pub fn compile_extern<'ctx>(function: &Arc<CodelessFinalizedFunction>, type_getter: &mut CompilerTypeGetter<'ctx>,
+                            value: FunctionValue<'ctx>) {
+    let mut params: Vec<BasicMetadataTypeEnum> = Vec::new();
+    for param in &function.arguments {
+        params.push(From::from(c_type(&param.field.field_type, type_getter)));
+    }
+
+    let returning = function.return_type.as_ref().map(|returning| c_type(returning, type_getter));
+    let c_function = match returning {
+        Some(returning) => returning.fn_type(params.as_slice(), false),
+        None => type_getter.compiler.context.void_type().fn_type(params.as_slice(), false)
+    };
+    let c_function = match type_getter.compiler.module.get_function(symbol_name(function)) {
+        Some(found) => found,
+        None => type_getter.compiler.module.add_function(symbol_name(function), c_function, Some(Linkage::External))
+    };
+
+    let block = type_getter.compiler.context.append_basic_block(value, "0");
+    type_getter.compiler.builder.position_at_end(block);
+
+    let mut arguments: Vec<BasicMetadataValueEnum> = Vec::new();
+    for (i, param) in value.get_params().into_iter().enumerate() {
+        let field_type = &function.arguments.get(i).unwrap().field.field_type;
+        arguments.push(if c_primitive(field_type, type_getter).is_some() && param.is_pointer_value() {
+            type_getter.compiler.builder.build_load(param.into_pointer_value(), &format!("{}", i + 1)).into()
+        } else {
+            param.into()
+        });
+    }
+
+    let result = type_getter.compiler.builder.build_call(c_function, arguments.as_slice(), "result")
+        .try_as_basic_value().left();
+    match (result, function.return_type.as_ref().and_then(|returning| c_primitive(returning, type_getter))) {
+        (Some(result), Some(primitive)) => {
+            let malloc = malloc_type(type_getter, primitive.ptr_type(AddressSpace::default()).const_zero(), &mut 0);
+            type_getter.compiler.builder.build_store(malloc, result);
+            type_getter.compiler.builder.build_return(Some(&malloc));
+        }
+        (Some(result), None) => {
+            type_getter.compiler.builder.build_return(Some(&result));
+        }
+        (None, _) => {
+            type_getter.compiler.builder.build_return(None);
+        }
+    }
+}
+
+/// The type C uses for a Raven type: numbers and bools by value, everything else as a pointer.
+fn c_type<'ctx>(types: &FinalizedTypes, type_getter: &mut CompilerTypeGetter<'ctx>) -> BasicTypeEnum<'ctx> {
+    return match c_primitive(types, type_getter) {
+        Some(found) => found,
+        None => type_getter.get_type(&FinalizedTypes::Reference(Box::new(dereference(types).clone())))
+    };
+}
+
+/// The value type of a number or bool, or None for other types.
+fn c_primitive<'ctx>(types: &FinalizedTypes, type_getter: &CompilerTypeGetter<'ctx>) -> Option<BasicTypeEnum<'ctx>> {
+    return get_internal_struct(type_getter.compiler.context, &dereference(types).name());
+}
+
+fn dereference(types: &FinalizedTypes) -> &FinalizedTypes {
+    return match types {
+        FinalizedTypes::Reference(inner) => inner.deref(),
+        other => other
+    };
+}
+
+/// The name of the C function an external function calls, which is its unqualified name so it links against C symbols.
+pub fn symbol_name(function: &CodelessFinalizedFunction) -> &str {
+    return function.data.name.split("::").last().unwrap();
+}
@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use std::fmt::{Debug, Display, Formatter};
 use anyhow::Error;
 use std::{fs, path};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use colored::Colorize;
 
 pub type Main<T> = unsafe extern "C" fn() -> T;
@@ -10,14 +12,69 @@ pub type Main<T> = unsafe extern "C" fn() -> T;
 pub struct RunnerSettings {
     pub sources: Vec<Box<dyn SourceSet>>,
     pub debug: bool,
-    pub compiler_arguments: CompilerArguments
+    pub compiler_arguments: CompilerArguments,
+    // If set, the time each phase takes is recorded here
+    pub timings: Option<Arc<Mutex<Timings>>>
+}
+
+/// The phases of compilation that timings are recorded for, in the order they run.
+/// Degenericing happens while verifying, so its time is also part of verify's.
+pub const PHASES: [&str; 5] = ["tokenize", "parse", "verify", "degeneric", "codegen"];
+
+/// The wall-clock time spent in each phase of compilation. Running the compiled code isn't included.
+#[derive(Clone, Debug)]
+pub struct Timings {
+    // The name of each phase and how long it took, in the order of PHASES
+    pub phases: Vec<(String, Duration)>
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        return Self {
+            phases: PHASES.iter().map(|phase| (phase.to_string(), Duration::ZERO)).collect()
+        };
+    }
+}
+
+impl Timings {
+    /// Adds the time since the start to the phase. Phases that run more than once, like tokenizing each file,
+    /// add up, so a phase running on multiple threads at once can take longer than the whole compile.
+    pub fn record(timings: &Option<Arc<Mutex<Timings>>>, phase: &str, start: Instant) {
+        if let Some(timings) = timings {
+            let elapsed = start.elapsed();
+            let mut timings = timings.lock().unwrap();
+            match timings.phases.iter_mut().find(|(name, _)| name == phase) {
+                Some((_, time)) => *time += elapsed,
+                None => timings.phases.push((phase.to_string(), elapsed))
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct CompilerArguments {
     pub compiler: String,
     pub target: String,
-    pub temp_folder: PathBuf
+    pub temp_folder: PathBuf,
+    // If the compiled module should be checked for invalid code before it's run
+    pub verify: bool,
+    // How much the compiled module should be optimized
+    pub optimization: Optimization,
+    // How deeply lines can be nested inside each other, or None for the parser's default
+    pub max_nesting: Option<usize>,
+    // How deep the trait solver can recurse, or None for the solver's default
+    pub solver_overflow_depth: Option<usize>,
+    // The largest a type can grow to in the trait solver, or None for the solver's default
+    pub solver_max_size: Option<usize>
+}
+
+/// How much the compiler optimizes its output, trading compile time for faster code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Optimization {
+    None,
+    Less,
+    Default,
+    Aggressive
 }
 
 pub struct Arguments {
@@ -112,6 +169,38 @@ impl SourceSet for FileSourceSet {
     }
 }
 
+/// A single source file kept in memory, for compiling source that isn't on disk.
+#[derive(Clone, Debug)]
+pub struct StringSourceSet {
+    // The module name of the file, used as the prefix of everything in it
+    pub name: String,
+    pub source: String,
+}
+
+impl Readable for StringSourceSet {
+    fn read(&self) -> String {
+        return self.source.clone();
+    }
+
+    fn path(&self) -> String {
+        return format!("{}.rv", self.name);
+    }
+}
+
+impl SourceSet for StringSourceSet {
+    fn get_files(&self) -> Vec<Box<dyn Readable>> {
+        return vec!(Box::new(self.clone()));
+    }
+
+    fn relative(&self, _other: &Box<dyn Readable>) -> String {
+        return self.name.clone();
+    }
+
+    fn cloned(&self) -> Box<dyn SourceSet> {
+        return Box::new(self.clone());
+    }
+}
+
 fn read_recursive(base: PathBuf, output: &mut Vec<Box<dyn Readable>>) -> Result<(), Error> {
     if fs::metadata(&base)?.file_type().is_dir() {
         for file in fs::read_dir(&base)? {
@@ -136,6 +225,8 @@ pub struct ParsingError {
     pub end: (u32, u32),
     pub end_offset: usize,
     pub message: String,
+    // If the parser can't recover from this error by trying to parse the code another way
+    pub fatal: bool,
 }
 
 impl ParsingError {
@@ -148,6 +239,7 @@ impl ParsingError {
             end: (0, 0),
             end_offset: 0,
             message: "You shouldn't see this! Report this please!".to_string(),
+            fatal: false,
         };
     }
 
@@ -160,10 +252,42 @@ impl ParsingError {
             end,
             end_offset,
             message,
+            fatal: false,
+        };
+    }
+
+    /// Renders the error with the line it's on and the span underlined, like:
+    /// error message
+    ///  --> file:line:column
+    ///   |
+    /// 1 | the line
+    ///   |     ^^^^
+    /// The source must be the contents of the error's file. Spans past the end of the line are underlined up to the end of it.
+    pub fn render(&self, source: &str) -> String {
+        let mut output = format!("{}\n --> {}:{}:{}\n", self.message, self.file, self.start.0, self.start.1);
+        let line = match source.split("\n").nth((self.start.0 as usize).max(1) - 1) {
+            Some(line) => line.trim_end_matches('\r'),
+            None => return output
         };
+
+        let number = self.start.0.to_string();
+        let padding = " ".repeat(number.len());
+        let column = (self.start.1 as usize).min(line.chars().count());
+        // Offsets are in bytes, but the line is rendered in characters.
+        let width = source.get(self.start_offset..self.end_offset)
+            .map_or(self.end_offset.saturating_sub(self.start_offset), |token| token.chars().count())
+            .min(line.chars().count() - column).max(1);
+        output += &format!("{} |\n{} | {}\n{} | {}{}\n", padding, number, line, padding, " ".repeat(column), "^".repeat(width));
+        return output;
     }
 
     pub fn print(&self, sources: &Vec<Box<dyn SourceSet>>) {
+        // Errors from after parsing, like the compiler, aren't in any file.
+        if self.file.is_empty() {
+            println!("{}", self.message.bright_red());
+            return;
+        }
+
         let mut file = None;
         'outer: for source in sources {
             for readable in source.get_files() {
@@ -184,8 +308,11 @@ impl ParsingError {
         println!("{}", format!("in file {}:{}:{}", file.path(), self.start.0, self.start.1).bright_red());
         println!("{} {}", " ".repeat(self.start.0.to_string().len()), "|".bright_cyan());
         println!("{} {} {}", self.start.0.to_string().bright_cyan(), "|".bright_cyan(), line.bright_red());
+        // Offsets are in bytes, but the line is printed in characters.
+        let width = contents.get(self.start_offset..self.end_offset)
+            .map_or(self.end_offset - self.start_offset, |token| token.chars().count());
         println!("{} {} {}{}", " ".repeat(self.start.0.to_string().len()), "|".bright_cyan(), " ".repeat(self.start.1 as usize),
-                 "^".repeat(self.end_offset-self.start_offset).bright_red());
+                 "^".repeat(width).bright_red());
     }
 }
 
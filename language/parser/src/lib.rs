@@ -4,17 +4,20 @@ extern crate core;
 
 use std::collections::HashMap;
 use std::sync::Arc; use std::sync::Mutex;
+use std::time::Instant;
 use syntax::async_util::{HandleWrapper, NameResolver, UnparsedType};
 use syntax::syntax::Syntax;
 use crate::parser::top_parser::parse_top;
 use crate::parser::util::ParserUtils;
-use crate::tokens::tokenizer::Tokenizer;
+use crate::tokens::tokenizer::{ParserState, Tokenizer, TokenizerState};
 use crate::tokens::tokens::TokenTypes;
 
 pub mod parser;
 pub mod tokens;
 
-pub async fn parse(syntax: Arc<Mutex<Syntax>>, handle: Arc<Mutex<HandleWrapper>>, name: String, file: String) {
+/// Parses the file into the syntax. Lines nested deeper than max_depth are an error, MAX_NESTING_DEPTH is a good default.
+pub async fn parse(syntax: Arc<Mutex<Syntax>>, handle: Arc<Mutex<HandleWrapper>>, name: String, file: String, max_depth: usize) {
+    let start = Instant::now();
     let mut tokenizer = Tokenizer::new(file.as_bytes());
     let mut tokens = Vec::new();
     loop {
@@ -24,6 +27,71 @@ pub async fn parse(syntax: Arc<Mutex<Syntax>>, handle: Arc<Mutex<HandleWrapper>>
         }
     }
 
+    if let Some(opening) = &tokenizer.unterminated_comment {
+        syntax.lock().unwrap().add_error(opening.make_error(name.clone(), "Unterminated block comment!".to_string()));
+    }
+    syntax.lock().unwrap().record_timing("tokenize", start);
+
+    let start = Instant::now();
+    let mut parser_utils = ParserUtils {
+        buffer: file.as_bytes(),
+        index: 0,
+        tokens,
+        syntax,
+        file: name.clone(),
+        imports: ImportNameResolver::new(name.clone()),
+        handle,
+        loops: Vec::new(),
+        depth: 0,
+        max_depth
+    };
+
+    parse_top(&mut parser_utils);
+    parser_utils.syntax.lock().unwrap().record_timing("parse", start);
+}
+
+/// The result of parsing part of a file with parse_incremental.
+pub enum IncrementalParse {
+    // Every element in the input was parsed, and more input is parsed from this state
+    Complete(ParserState),
+    // The input ended partway through an element. Every element before it was parsed,
+    // and the unfinished one is parsed from this state once the rest of it is added
+    NeedsMoreInput(ParserState),
+}
+
+/// Parses the top elements of a file one piece at a time, for things like an interactive shell.
+/// The first call passes no state, and later calls pass the returned state with more source added to the end of the file.
+/// Parsed elements are added to the syntax like in parse. Imports only apply to elements parsed in the same call.
+pub fn parse_incremental(syntax: Arc<Mutex<Syntax>>, handle: Arc<Mutex<HandleWrapper>>, name: String, file: &str,
+                         state: Option<&ParserState>, max_depth: usize) -> IncrementalParse {
+    let mut tokenizer = Tokenizer::new(file.as_bytes());
+    if let Some(state) = state {
+        tokenizer.load(state);
+    }
+
+    let mut tokens = Vec::new();
+    // The amount of tokens in complete elements, and the state after them
+    let mut complete = (0, tokenizer.serialize());
+    let end = loop {
+        let token = tokenizer.next();
+        if token.token_type == TokenTypes::EOF {
+            break token;
+        }
+        // Comments are only counted between elements, so a comment after a modifier doesn't split it from its element.
+        let ends_element = matches!(token.token_type, TokenTypes::CodeEnd | TokenTypes::StructEnd | TokenTypes::ImportEnd) ||
+            (token.token_type == TokenTypes::Comment && complete.0 == tokens.len());
+        tokens.push(token);
+        // Elements are only finished once the tokenizer is back at the top of the file.
+        if ends_element && tokenizer.state == TokenizerState::TOP_ELEMENT && tokenizer.bracket_depth == 0 {
+            complete = (tokens.len(), tokenizer.serialize());
+        }
+    }
+
+    let finished = complete.0 == tokens.len() && tokenizer.unterminated_comment.is_none();
+    tokens.truncate(complete.0);
+    // The unfinished element is left for the next call, so the parser sees the file end after the last complete one.
+    tokens.push(end);
+
     let mut parser_utils = ParserUtils {
         buffer: file.as_bytes(),
         index: 0,
@@ -31,10 +99,19 @@ pub async fn parse(syntax: Arc<Mutex<Syntax>>, handle: Arc<Mutex<HandleWrapper>>
         syntax,
         file: name.clone(),
         imports: ImportNameResolver::new(name.clone()),
-        handle
+        handle,
+        loops: Vec::new(),
+        depth: 0,
+        max_depth
     };
 
     parse_top(&mut parser_utils);
+
+    return if finished {
+        IncrementalParse::Complete(complete.1)
+    } else {
+        IncrementalParse::NeedsMoreInput(complete.1)
+    };
 }
 
 #[derive(Clone)]
@@ -72,4 +149,4 @@ impl NameResolver for ImportNameResolver {
     fn boxed_clone(&self) -> Box<dyn NameResolver> {
         return Box::new(self.clone());
     }
-}
\ No newline at end of file
+}
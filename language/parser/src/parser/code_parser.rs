@@ -2,7 +2,7 @@ use syntax::code::{Effects, Expression, ExpressionType};
 use syntax::function::CodeBody;
 use syntax::ParsingError;
 use syntax::async_util::UnparsedType;
-use crate::parser::control_parser::{parse_do_while, parse_for, parse_if, parse_while};
+use crate::parser::control_parser::{parse_do_while, parse_for, parse_if, parse_switch, parse_while};
 use crate::parser::operator_parser::parse_operator;
 use crate::parser::util::{add_generics, ParserUtils};
 use crate::tokens::tokens::{Token, TokenTypes};
@@ -45,6 +45,22 @@ pub enum ParseState {
 
 pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
                   -> Result<Option<Expression>, ParsingError> {
+    // Operators parse their right side as another line, so a chain like 1 + 2 + 3 is nested too.
+    if parser_utils.depth >= parser_utils.max_depth {
+        let token = parser_utils.tokens.get(parser_utils.index).unwrap();
+        let mut error = token.make_error(parser_utils.file.clone(), "Expression nesting too deep!".to_string());
+        error.fatal = true;
+        return Err(error);
+    }
+
+    parser_utils.depth += 1;
+    let output = parse_line_inner(parser_utils, state);
+    parser_utils.depth -= 1;
+    return output;
+}
+
+fn parse_line_inner(parser_utils: &mut ParserUtils, state: ParseState)
+                    -> Result<Option<Expression>, ParsingError> {
     // The current effect
     let mut effect: Option<Effects> = None;
     // The current type of expression
@@ -83,8 +99,7 @@ pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
                     _ => if let Some(expression) = parse_line(parser_utils, ParseState::None)? {
                         effect = Some(Effects::Paren(Box::new(expression.effect)));
                     } else {
-                        //effect = None;
-                        panic!("Unknown code path - report this!");
+                        return Err(token.make_error(parser_utils.file.clone(), "Expected an effect in the parenthesis!".to_string()));
                     }
                 }
             }
@@ -92,19 +107,21 @@ pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
                 if effect.is_some() {
                     return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected float! Did you forget a semicolon?")));
                 }
-                effect = Some(Effects::Float(token.to_string(parser_utils.buffer).parse().unwrap()))
+                let found = strip_separators(&token, parser_utils)?;
+                effect = Some(Effects::Float(found.parse().map_err(|error|
+                    token.make_error(parser_utils.file.clone(), format!("Invalid float {}: {}", found, error)))?))
             }
             TokenTypes::Integer => {
                 if effect.is_some() {
                     return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected integer! Did you forget a semicolon? {:?}", effect.unwrap())));
                 }
-                effect = Some(Effects::Int(token.to_string(parser_utils.buffer).parse().unwrap()))
+                effect = Some(Effects::Int(parse_integer(&token, parser_utils)?))
             }
             TokenTypes::Char => {
                 if effect.is_some() {
-                    return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected boolean! Did you forget a semicolon?")));
+                    return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected character! Did you forget a semicolon?")));
                 }
-                effect = Some(Effects::Char(token.to_string(parser_utils.buffer).as_bytes()[1] as char))
+                effect = Some(Effects::Char(parse_char(&token, parser_utils)?))
             }
             TokenTypes::True => {
                 if effect.is_some() {
@@ -138,11 +155,17 @@ pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
             }
             TokenTypes::Variable => {
                 let next = parser_utils.tokens.get(parser_utils.index).unwrap();
+                if next.token_type == TokenTypes::Operator && next.to_string(parser_utils.buffer) == "!" &&
+                    parser_utils.tokens.get(parser_utils.index + 1).map_or(false, |after| after.token_type == TokenTypes::ParenOpen) {
+                    return Err(token.make_error(parser_utils.file.clone(),
+                                                format!("Macros like {}!() aren't supported, call a function instead!",
+                                                        token.to_string(parser_utils.buffer))));
+                }
                 if let TokenTypes::ParenOpen = next.token_type {
                     //Skip because ParenOpen handles this.
                 } else if let TokenTypes::Operator = next.token_type {
                     //Skip if a generic method is being called next to preserve the last effect.
-                     if is_generic(&token, parser_utils) {
+                     if is_generic(parser_utils) {
                          continue
                      } else {
                         effect = Some(
@@ -160,6 +183,28 @@ pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
             TokenTypes::Return => {
                 expression_type = ExpressionType::Return
             }
+            TokenTypes::Break | TokenTypes::Continue => {
+                let name = if token.token_type == TokenTypes::Break { "break" } else { "continue" };
+                if effect.is_some() {
+                    return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected {}! Did you forget a semicolon?", name)));
+                }
+
+                let target = match parser_utils.loops.last() {
+                    Some((continuing, breaking)) =>
+                        if token.token_type == TokenTypes::Break { breaking.clone() } else { continuing.clone() },
+                    None => return Err(token.make_error(parser_utils.file.clone(), format!("Can't {} outside of a loop!", name)))
+                };
+
+                // Loops aren't expressions, so there's nothing a value could be given to.
+                if parser_utils.tokens.get(parser_utils.index).unwrap().token_type != TokenTypes::LineEnd {
+                    return Err(token.make_error(parser_utils.file.clone(),
+                                                format!("Expected a semicolon after {}!", name)));
+                }
+                parser_utils.index += 1;
+
+                // Breaks jump to the end of the innermost loop, continues jump to its condition.
+                return Ok(Some(Expression::new(ExpressionType::Line, Effects::Jump(target))));
+            }
             TokenTypes::New => {
                 if effect.is_some() {
                     return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected new! Did you forget a semicolon?")));
@@ -200,6 +245,18 @@ pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
                 }
                 return Ok(Some(Expression::new(expression_type, expression.effect)));
             }
+            TokenTypes::Switch => {
+                if effect.is_some() {
+                    return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected switch! Did you forget a semicolon?")));
+                }
+
+                let expression = parse_switch(parser_utils)?;
+                // If every arm returns/breaks, the outer block should too
+                if expression_type == ExpressionType::Line {
+                    expression_type = expression.expression_type;
+                }
+                return Ok(Some(Expression::new(expression_type, expression.effect)));
+            }
             TokenTypes::For => {
                 if effect.is_some() {
                     return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected for! Did you forget a semicolon?")));
@@ -245,7 +302,7 @@ pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
                 // Example: test<Value>()
                 parser_utils.index -= 1;
                 if (last.token_type == TokenTypes::Variable || last.token_type == TokenTypes::CallingType) &&
-                    is_generic(&parser_utils.tokens[parser_utils.index-1], parser_utils) {
+                    is_generic(parser_utils) {
                      parser_utils.index += 1;
                      effect = Some(parse_generic_method(effect, parser_utils)?);
                  } else {
@@ -263,7 +320,7 @@ pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
             TokenTypes::ArgumentEnd => break,
             TokenTypes::CallingType => {
                 let next: &Token = parser_utils.tokens.get(parser_utils.index).unwrap();
-                if next.token_type == TokenTypes::ParenOpen || is_generic(&token, parser_utils) {
+                if next.token_type == TokenTypes::ParenOpen || is_generic(parser_utils) {
                     // Ignored, ParenOpen or Operator handles this
                 } else {
                     if effect.is_none() {
@@ -289,7 +346,9 @@ pub fn parse_line(parser_utils: &mut ParserUtils, state: ParseState)
                 }
             },
             TokenTypes::Comment => {}
-            _ => panic!("How'd you get here? {:?}", token.token_type)
+            TokenTypes::InvalidCharacters => return Err(token.make_error(parser_utils.file.clone(),
+                                                                         "Invalid characters!".to_string())),
+            _ => return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected {:?}!", token.token_type)))
         }
     }
 
@@ -308,7 +367,9 @@ fn parse_string(parser_utils: &mut ParserUtils) -> Result<Effects, ParsingError>
 
         match token.token_type {
             TokenTypes::StringEnd => {
-                // End of string, must have a null character at the end
+                // End of string, must have a null character at the end.
+                // A \0 written in the string is kept in the constant, but strings are C strings at runtime,
+                // so everything after it is cut off (see escape-characters.rv).
                 let found = token.to_string(parser_utils.buffer);
                 string += &found[0..found.len() - 1];
                 return Ok(Effects::String(string + "\0"));
@@ -316,53 +377,110 @@ fn parse_string(parser_utils: &mut ParserUtils) -> Result<Effects, ParsingError>
             TokenTypes::StringEscape => {
                 // Escape token
 
-                // get the text from the Raven file starting at the last token up to the current escape character
+                // get the text from the Raven file starting at the last token up to the end of the escape
                 let found = token.to_string(parser_utils.buffer);
 
-                // check if it a hex value, because if it is, then it will 4 characters long (\xAA)
-                let is_hex = found.len() >= 3 && &found[found.len() - 3..found.len() - 2] == "x";
-                let string_end = found.len() - (if is_hex { 4 } else { 2 });
+                // every escape ends its token, so the first \ in the token is the start of the escape
+                let escape_start = found.find('\\').unwrap();
 
                 // add the text to the string, because this text is part of the string in the Raven Code
-                string += &found[0..string_end];
+                string += &found[0..escape_start];
 
-                // match the character after the \ to see what type of escape character it is
-                let index = if is_hex { found.len() - 3 } else { found.len() - 1 };
-                match &found[index..index + 1] {
-                    "n" => {
-                        string += "\n";
-                    }
-                    "t" => {
-                        string += "\t";
-                    }
-                    "r" => {
-                        string += "\r";
-                    }
-                    "\\" => {
-                        string += "\\";
-                    }
-                    "\'" => {
-                        string += "\'";
-                    }
-                    "\"" => {
-                        string += "\"";
-                    }
-                    "x" => {
-                        // Convert the hex to a character, and append it to the string
-                        string.push(u8::from_str_radix(&found[found.len() - 2..found.len()], 16).expect("Unexpected hex value") as char);
-                    }
-                    _ => {
-                        // not a supported character
-                        panic!("Unexpected escape character: {}", parser_utils.buffer[token.end_offset - 1] as char)
+                let escape = &found[escape_start..];
+                match parse_escape(&escape[1..]) {
+                    Some(character) => string.push(character),
+                    None => {
+                        // not a supported escape, point the error at just the escape
+                        let length = escape.chars().count() as u32;
+                        return Err(ParsingError::new(parser_utils.file.clone(),
+                                                     (token.end.0, token.end.1 - length), token.end_offset - escape.len(),
+                                                     token.end, token.end_offset,
+                                                     format!("Invalid escape sequence: {}", escape)));
                     }
                 }
             }
             TokenTypes::StringStart => {} //the first token is always a StringStart, so skip this
-            _ => panic!("How'd you get here? {:?}", token.token_type)
+            TokenTypes::EOF => return Err(token.make_error(parser_utils.file.clone(), "Unterminated string!".to_string())),
+            _ => return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected {:?}!", token.token_type)))
         }
     }
 }
 
+/// Decodes an integer literal, which can be hexadecimal (0x), binary (0b), or octal (0o).
+pub fn parse_integer(token: &Token, parser_utils: &ParserUtils) -> Result<i64, ParsingError> {
+    let found = strip_separators(token, parser_utils)?;
+    let (digits, radix) = match found.get(0..2) {
+        Some("0x") => (&found[2..], 16),
+        Some("0b") => (&found[2..], 2),
+        Some("0o") => (&found[2..], 8),
+        _ => (found.as_str(), 10)
+    };
+
+    return i64::from_str_radix(digits, radix).map_err(|error|
+        token.make_error(parser_utils.file.clone(), format!("Invalid integer {}: {}", found, error)));
+}
+
+/// Removes the underscores separating digits in a number, like 1_000_000.
+/// Each underscore must be between two digits, so 5_, 5__0, and 0x_F are errors.
+fn strip_separators(token: &Token, parser_utils: &ParserUtils) -> Result<String, ParsingError> {
+    let found = token.to_string(parser_utils.buffer);
+    let bytes = found.as_bytes();
+    for (i, character) in bytes.iter().enumerate() {
+        if *character == b'_' && (i == 0 || i == bytes.len() - 1 ||
+            !bytes[i - 1].is_ascii_hexdigit() || !bytes[i + 1].is_ascii_hexdigit()) {
+            return Err(token.make_error(parser_utils.file.clone(),
+                                        format!("Digit separators must be between two digits in {}!", found)));
+        }
+    }
+    return Ok(found.replace('_', ""));
+}
+
+/// Decodes a character literal, which must be a single character or escape between single quotes.
+fn parse_char(token: &Token, parser_utils: &ParserUtils) -> Result<char, ParsingError> {
+    let found = token.to_string(parser_utils.buffer);
+    // The token starts at the end of the last token, so skip any whitespace before the quote.
+    let found = found.trim_start();
+    let inner = &found[1..found.len() - 1];
+    let character = match inner.strip_prefix('\\') {
+        Some(escape) => parse_escape(escape),
+        None => {
+            let mut characters = inner.chars();
+            characters.next().filter(|_| characters.next().is_none())
+        }
+    };
+
+    return character.ok_or_else(|| token.make_error(parser_utils.file.clone(),
+                                                    format!("Invalid character literal {}!", found)));
+}
+
+/// Decodes the escape after the \ into the character it represents, or None if it isn't a valid escape.
+/// Hex escapes are limited to ASCII (\x00 to \x7F), use a unicode escape like \u{E9} for anything else.
+fn parse_escape(escape: &str) -> Option<char> {
+    return match escape {
+        "n" => Some('\n'),
+        "t" => Some('\t'),
+        "r" => Some('\r'),
+        "0" => Some('\0'),
+        "\\" => Some('\\'),
+        "\'" => Some('\''),
+        "\"" => Some('\"'),
+        _ => if let Some(hex) = escape.strip_prefix('x') {
+            if hex.len() != 2 {
+                return None;
+            }
+            // Above 0x7F the character would be written as two UTF-8 bytes instead of the one byte asked for.
+            u8::from_str_radix(hex, 16).ok().filter(|value| value.is_ascii()).map(|value| value as char)
+        } else if let Some(unicode) = escape.strip_prefix("u{").and_then(|inner| inner.strip_suffix('}')) {
+            if unicode.is_empty() || unicode.len() > 6 {
+                return None;
+            }
+            u32::from_str_radix(unicode, 16).ok().and_then(char::from_u32)
+        } else {
+            None
+        }
+    };
+}
+
 /// Parses a generic method call
 fn parse_generic_method(effect: Option<Effects>, parser_utils: &mut ParserUtils)
                         -> Result<Effects, ParsingError> {
@@ -436,18 +554,26 @@ fn parse_new(parser_utils: &mut ParserUtils) -> Result<Effects, ParsingError> {
             }
             //Handle making new structs with generics.
             TokenTypes::Operator => {
-                types = Some(add_generics(types.unwrap().to_string(), parser_utils).0);
+                let base = match types {
+                    Some(base) => base.to_string(),
+                    None => return Err(token.make_error(parser_utils.file.clone(), "Expected a type before the generics!".to_string()))
+                };
+                types = Some(add_generics(base, parser_utils).0);
             }
             TokenTypes::BlockStart => {
                 values = parse_new_args(parser_utils)?;
                 break;
             }
             TokenTypes::InvalidCharacters => {}
-            _ => panic!("How'd you get here? {:?}", token.token_type)
+            _ => return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected {:?}!", token.token_type)))
         }
     }
 
-    return Ok(Effects::CreateStruct(types.unwrap(), values));
+    return match types {
+        Some(types) => Ok(Effects::CreateStruct(types, values)),
+        None => Err(parser_utils.tokens.get(parser_utils.index - 1).unwrap().make_error(parser_utils.file.clone(),
+                                                                               "Expected a type after new!".to_string()))
+    };
 }
 
 fn parse_new_args(parser_utils: &mut ParserUtils) -> Result<Vec<(String, Effects)>, ParsingError> {
@@ -480,7 +606,7 @@ fn parse_new_args(parser_utils: &mut ParserUtils) -> Result<Vec<(String, Effects
             }
             TokenTypes::InvalidCharacters => {}
             TokenTypes::Comment => {}
-            _ => panic!("How'd you get here? {:?}", token.token_type)
+            _ => return Err(token.make_error(parser_utils.file.clone(), format!("Unexpected {:?}!", token.token_type)))
         }
 
     }
@@ -488,7 +614,36 @@ fn parse_new_args(parser_utils: &mut ParserUtils) -> Result<Vec<(String, Effects
     return Ok(values);
 }
 
-fn is_generic(token: &Token, parser_utils: &ParserUtils) -> bool {
+/// Checks if the < after the token starts the generics of a method call instead of a comparison.
+/// Looks ahead for the matching > and only treats it as generic if the call's arguments follow directly.
+/// Ex: test<Value>() is a generic call, but test < value and test <value > other are comparisons.
+/// Paths and nested generics like test<core::List<Value>>() work, but arrays don't since add_generics can't parse them.
+fn is_generic(parser_utils: &ParserUtils) -> bool {
     let next: &Token = parser_utils.tokens.get(parser_utils.index).unwrap();
-    return parser_utils.buffer[token.end_offset] != b' ' && next.to_string(parser_utils.buffer) == "<";
+    if next.token_type != TokenTypes::Operator || next.to_string(parser_utils.buffer) != "<" {
+        return false;
+    }
+
+    let mut depth = 0;
+    let mut index = parser_utils.index;
+    while let Some(checking) = parser_utils.tokens.get(index) {
+        index += 1;
+        match checking.token_type {
+            TokenTypes::Variable | TokenTypes::ArgumentEnd => {}
+            TokenTypes::Operator => match checking.to_string(parser_utils.buffer).as_str() {
+                "<" => depth += 1,
+                ">" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return parser_utils.tokens.get(index)
+                            .map_or(false, |after| after.token_type == TokenTypes::ParenOpen);
+                    }
+                }
+                // Any other operator can't be inside of generics.
+                _ => return false
+            },
+            _ => return false
+        }
+    }
+    return false;
 }
\ No newline at end of file
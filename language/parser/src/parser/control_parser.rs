@@ -1,9 +1,11 @@
+use std::mem;
+
 use syntax::code::{Effects, Expression, ExpressionType};
 use syntax::function::CodeBody;
 use syntax::ParsingError;
 
 use crate::{ParserUtils, TokenTypes};
-use crate::parser::code_parser::{parse_code, parse_line, ParseState};
+use crate::parser::code_parser::{parse_code, parse_integer, parse_line, ParseState};
 
 /// Parses an if statement into a single expression.
 pub fn parse_if(parser_utils: &mut ParserUtils) -> Result<Expression, ParsingError> {
@@ -85,6 +87,156 @@ pub fn parse_if(parser_utils: &mut ParserUtils) -> Result<Expression, ParsingErr
                                                    parser_utils.imports.last_id - adding)?));
 }
 
+/// Parses a switch statement into a single expression.
+/// ex:
+/// switch value {
+///     1 => { ... }
+///     _ => { ... }
+/// }
+/// Each arm is an integer or boolean literal, or the _ wildcard, and every literal has to be the same type.
+/// The value is stored in a variable and the switch becomes an if-else chain comparing the variable to each literal.
+/// The tokenizer already reserves switch for this, so there's no separate match keyword, and lowering it here
+/// to the same effects as if means the checker and compilers don't need a match effect.
+/// Because of that, a switch over a boolean missing an arm is caught here instead of while verifying.
+pub fn parse_switch(parser_utils: &mut ParserUtils) -> Result<Expression, ParsingError> {
+    let start = parser_utils.tokens.get(parser_utils.index - 1).unwrap().clone();
+    let effect = parse_line(parser_utils, ParseState::ControlVariable)?;
+    if effect.is_none() {
+        return Err(parser_utils.tokens.get(parser_utils.index).unwrap()
+            .make_error(parser_utils.file.clone(), "Expected value, found void".to_string()));
+    }
+
+    if parser_utils.tokens.get(parser_utils.index).unwrap().token_type != TokenTypes::BlockStart {
+        return Err(parser_utils.tokens.get(parser_utils.index).unwrap()
+            .make_error(parser_utils.file.clone(), "Expected switch arms, found void".to_string()));
+    }
+
+    parser_utils.index += 1;
+
+    let mut arms: Vec<(Effects, CodeBody)> = Vec::new();
+    let mut wildcard = None;
+    let mut returning = None;
+    loop {
+        let token = parser_utils.tokens.get(parser_utils.index).unwrap().clone();
+        parser_utils.index += 1;
+
+        // None is the wildcard
+        let pattern = match token.token_type {
+            TokenTypes::BlockEnd => break,
+            TokenTypes::Comment | TokenTypes::ArgumentEnd => continue,
+            TokenTypes::Integer => Some(Effects::Int(parse_integer(&token, parser_utils)?)),
+            TokenTypes::Operator if token.to_string(parser_utils.buffer) == "-" &&
+                parser_utils.tokens.get(parser_utils.index).unwrap().token_type == TokenTypes::Integer => {
+                parser_utils.index += 1;
+                Some(Effects::Int(-parse_integer(parser_utils.tokens.get(parser_utils.index - 1).unwrap(), parser_utils)?))
+            }
+            TokenTypes::True => Some(Effects::Bool(true)),
+            TokenTypes::False => Some(Effects::Bool(false)),
+            TokenTypes::Variable if token.to_string(parser_utils.buffer) == "_" => None,
+            _ => return Err(token.make_error(parser_utils.file.clone(),
+                                             "Expected a literal or _ for the switch arm!".to_string()))
+        };
+
+        if wildcard.is_some() {
+            return Err(token.make_error(parser_utils.file.clone(), "Unreachable switch arm after _!".to_string()));
+        }
+
+        // Each pattern is followed by => and the arm's body
+        if parser_utils.tokens.get(parser_utils.index).unwrap().token_type != TokenTypes::Equals ||
+            parser_utils.tokens.get(parser_utils.index + 1).unwrap().to_string(parser_utils.buffer) != ">" {
+            return Err(token.make_error(parser_utils.file.clone(), "Expected => after the switch pattern!".to_string()));
+        }
+
+        if parser_utils.tokens.get(parser_utils.index + 2).unwrap().token_type != TokenTypes::BlockStart {
+            return Err(token.make_error(parser_utils.file.clone(), "Expected body for the switch arm!".to_string()));
+        }
+
+        parser_utils.index += 3;
+        let (arm_returning, body) = parse_code(parser_utils)?;
+        // The switch only returns if every arm does.
+        returning = match returning {
+            Some(found) if found != arm_returning => Some(ExpressionType::Line),
+            _ => Some(arm_returning)
+        };
+
+        match pattern {
+            Some(pattern) => {
+                // Otherwise a boolean switch with an integer arm could look like it covers both booleans.
+                if arms.first().map_or(false, |(first, _)| mem::discriminant(first) != mem::discriminant(&pattern)) {
+                    return Err(token.make_error(parser_utils.file.clone(),
+                                                "Switch arms must all be integers or all be booleans!".to_string()));
+                }
+                if arms.iter().any(|(found, _)| same_pattern(found, &pattern)) {
+                    return Err(token.make_error(parser_utils.file.clone(), "Duplicate switch arm!".to_string()));
+                }
+                arms.push((pattern, body));
+            }
+            None => wildcard = Some(body)
+        }
+    }
+
+    // Booleans only have two values, so there's no reason to let one fall through silently.
+    if wildcard.is_none() && arms.iter().any(|(pattern, _)| matches!(pattern, Effects::Bool(_))) {
+        if arms.len() != 2 {
+            return Err(start.make_error(parser_utils.file.clone(),
+                                        "Switch over a boolean must cover true and false or have a _ arm!".to_string()));
+        }
+        // The last arm is the only value left, so it doesn't need to be checked.
+        wildcard = Some(arms.pop().unwrap().1);
+    }
+
+    let mut returning = returning.ok_or_else(||
+        start.make_error(parser_utils.file.clone(), "Switch has no arms!".to_string()))?;
+    // If there is no wildcard, the switch can't be the return.
+    if wildcard.is_none() {
+        returning = ExpressionType::Line;
+    }
+
+    parser_utils.imports.last_id += 1;
+    let variable = format!("$switch{}", parser_utils.imports.last_id - 1);
+    let value = Expression::new(ExpressionType::Line,
+                                Effects::CreateVariable(variable.clone(), Box::new(effect.unwrap().effect)));
+
+    if arms.is_empty() {
+        let mut body = wildcard.unwrap();
+        body.expressions.insert(0, value);
+        return Ok(Expression::new(returning, Effects::CodeBody(body)));
+    }
+
+    let else_body = match wildcard {
+        Some(body) => body,
+        None => {
+            parser_utils.imports.last_id += 1;
+            CodeBody::new(Vec::new(), (parser_utils.imports.last_id - 1).to_string())
+        }
+    };
+
+    let compare = |pattern: Effects| Effects::Operation("{}=={}".to_string(),
+                                                        vec!(Effects::LoadVariable(variable.clone()), pattern));
+    let (first, body) = arms.remove(0);
+    let else_ifs: Vec<(Effects, CodeBody)> = arms.into_iter()
+        .map(|(pattern, body)| (compare(pattern), body)).collect();
+
+    let adding = 2 + else_ifs.len() as u32;
+    parser_utils.imports.last_id += adding;
+    let mut effect = create_if(compare(first), body, else_ifs, Some(else_body),
+                               parser_utils.imports.last_id - adding)?;
+    // Store the value before any of the comparisons.
+    if let Effects::CodeBody(top) = &mut effect {
+        top.expressions.insert(0, value);
+    }
+    return Ok(Expression::new(returning, effect));
+}
+
+/// Checks if two switch patterns match the same value.
+fn same_pattern(first: &Effects, second: &Effects) -> bool {
+    return match (first, second) {
+        (Effects::Int(first), Effects::Int(second)) => first == second,
+        (Effects::Bool(first), Effects::Bool(second)) => first == second,
+        _ => false
+    };
+}
+
 pub fn parse_for(parser_utils: &mut ParserUtils) -> Result<Effects, ParsingError> {
     let name = parser_utils.tokens.get(parser_utils.index).unwrap();
     parser_utils.index += 1;
@@ -117,13 +269,15 @@ pub fn parse_for(parser_utils: &mut ParserUtils) -> Result<Effects, ParsingError
     }
     parser_utils.index += 1;
 
-    // Parses the body of the for loop
-    let body = parse_code(parser_utils)?.1;
+    // The loop's labels are picked before the body so breaks inside it know where to jump.
     parser_utils.imports.last_id += 2;
+    let id = parser_utils.imports.last_id - 2;
+
+    // Parses the body of the for loop
+    let body = parse_loop_body(parser_utils, (id + 1).to_string(), id)?;
 
     // Returns the finished for loop.
-    return create_for(name, effect.unwrap().effect,
-                      body, parser_utils.imports.last_id - 2);
+    return create_for(name, effect.unwrap().effect, body, id);
 }
 
 pub fn parse_while(parser_utils: &mut ParserUtils) -> Result<Effects, ParsingError> {
@@ -140,9 +294,10 @@ pub fn parse_while(parser_utils: &mut ParserUtils) -> Result<Effects, ParsingErr
 
     parser_utils.index += 1;
 
-    let (_returning, body) = parse_code(parser_utils)?;
     parser_utils.imports.last_id += 1;
-    return create_while(effect.unwrap().effect, body, parser_utils.imports.last_id - 1);
+    let id = parser_utils.imports.last_id - 1;
+    let body = parse_loop_body(parser_utils, id.to_string(), id)?;
+    return create_while(effect.unwrap().effect, body, id);
 }
 
 
@@ -153,7 +308,9 @@ pub fn parse_do_while(parser_utils: &mut ParserUtils) -> Result<Effects, Parsing
     }
     parser_utils.index += 1;
 
-    let (_returning, body) = parse_code(parser_utils)?;
+    parser_utils.imports.last_id += 2;
+    let id = parser_utils.imports.last_id - 2;
+    let body = parse_loop_body(parser_utils, (id + 1).to_string(), id)?;
 
     if parser_utils.tokens.get(parser_utils.index).unwrap().token_type != TokenTypes::While {
         return Err(parser_utils.tokens.get(parser_utils.index).unwrap()
@@ -168,18 +325,28 @@ pub fn parse_do_while(parser_utils: &mut ParserUtils) -> Result<Effects, Parsing
             .make_error(parser_utils.file.clone(), "Expected condition, found void".to_string()));
     }
 
-    parser_utils.imports.last_id += 1;
-    return create_do_while(effect.unwrap().effect, body, parser_utils.imports.last_id - 1);
+    return create_do_while(effect.unwrap().effect, body, id);
+}
+
+/// Parses the body of a loop with the given id, letting breaks inside it jump to the end of the loop
+/// and continues jump to the continuing label.
+fn parse_loop_body(parser_utils: &mut ParserUtils, continuing: String, id: u32) -> Result<CodeBody, ParsingError> {
+    parser_utils.loops.push((continuing, id.to_string() + "end"));
+    let body = parse_code(parser_utils);
+    parser_utils.loops.pop();
+    return Ok(body?.1);
 }
 
 fn create_do_while(effect: Effects, mut body: CodeBody, id: u32) -> Result<Effects, ParsingError> {
     let mut top = Vec::new();
 
-    let label = body.label.clone();
-    body.expressions.push(Expression::new(ExpressionType::Line, Effects::Jump((id - 1).to_string() + "end")));
+    // The condition gets its own block so continues can jump to it.
+    let condition = CodeBody::new(vec!(Expression::new(ExpressionType::Line, Effects::CompareJump(
+        Box::new(effect), body.label.clone(), id.to_string() + "end"))), (id + 1).to_string());
+    top.push(Expression::new(ExpressionType::Line, Effects::Jump(body.label.clone())));
+    body.expressions.push(Expression::new(ExpressionType::Line, Effects::Jump(condition.label.clone())));
     top.push(Expression::new(ExpressionType::Line, Effects::CodeBody(body)));
-    top.push(Expression::new(ExpressionType::Line, Effects::CompareJump(Box::new(effect),
-                                                                        label, id.to_string() + "end")));
+    top.push(Expression::new(ExpressionType::Line, Effects::CodeBody(condition)));
 
     return Ok(Effects::CodeBody(CodeBody::new(top, id.to_string())));
 }
@@ -257,8 +424,10 @@ fn create_if(effect: Effects, body: CodeBody,
 fn create_for(name: String, effect: Effects, mut body: CodeBody, id: u32) -> Result<Effects, ParsingError> {
     let mut top = Vec::new();
     let variable = format!("$iter{}", id);
+    // Anything implementing IntoIter can be looped over, like arrays or iterators.
     top.insert(0, Expression::new(ExpressionType::Line,
-                                  Effects::CreateVariable(variable.clone(), Box::new(effect))));
+                                  Effects::CreateVariable(variable.clone(), Box::new(Effects::ImplementationCall(
+                                      Box::new(effect), "iter::IntoIter".to_string(), "into_iter".to_string(), vec!(), None)))));
     top.push(Expression::new(ExpressionType::Line,
     Effects::Jump((id + 1).to_string())));
     // Adds a call to the Iter::next function at the top of the for loop.
@@ -38,6 +38,20 @@ pub fn parse_function(parser_utils: &mut ParserUtils, trait_function: bool, attr
                         panic!("No parent for {}!", name);
                     }
 
+                    // Receivers can be written as self, &self, or &mut self.
+                    // There are no moves or mutable bindings yet, and every structure is already passed by reference,
+                    // so all three are the same self argument for now.
+                    last_arg = match last_arg.as_str() {
+                        "self" | "&self" => "self".to_string(),
+                        // The mut must be its own word, so &mutself isn't a receiver.
+                        _ => match last_arg.strip_prefix("&mut") {
+                            Some(receiver) if receiver.starts_with(char::is_whitespace) && receiver.trim() == "self" =>
+                                "self".to_string(),
+                            _ => return Err(token.make_error(parser_utils.file.clone(),
+                                                             format!("Expected self, &self, or &mut self, found {}", last_arg)))
+                        }
+                    };
+
                     fields.push(Box::pin(to_field(parser_utils.get_struct(token,
                                                                                            parser_utils.imports.parent.as_ref().unwrap().clone()),
                                                                    Vec::new(), 0, last_arg)));
@@ -50,6 +64,9 @@ pub fn parse_function(parser_utils: &mut ParserUtils, trait_function: bool, attr
             }
             TokenTypes::ArgumentsEnd | TokenTypes::ReturnTypeArrow => {}
             TokenTypes::ReturnType => {
+                if parser_utils.buffer[token.start_offset..token.end_offset].iter().all(|character| character.is_ascii_whitespace()) {
+                    return Err(token.make_error(parser_utils.file.clone(), "Expected a return type after ->!".to_string()));
+                }
                 let ret_name = token.to_string(parser_utils.buffer).clone();
                 return_type = Some(parser_utils.get_struct(token, ret_name))
             }
@@ -68,6 +85,23 @@ pub fn parse_function(parser_utils: &mut ParserUtils, trait_function: bool, attr
     }
     let mut modifiers = get_modifier(modifiers.as_slice());
 
+    // #[extern("C")] declares a function from C, the same as the extern modifier.
+    if let Some(found) = Attribute::find_attribute("extern", &attributes) {
+        let abi = found.as_string_attribute().map_or("", |abi| abi.trim_matches('"'));
+        if abi != "C" {
+            return Err(parser_utils.tokens.get(parser_utils.index-1).unwrap().make_error(
+                parser_utils.file.clone(), format!("Unknown ABI {}, only \"C\" is supported!", abi)));
+        }
+        if !is_modifier(modifiers, Modifier::Extern) {
+            modifiers += Modifier::Extern as u8;
+        }
+    }
+
+    if is_modifier(modifiers, Modifier::Extern) && code.as_ref().map_or(false, |code| !code.expressions.is_empty()) {
+        return Err(parser_utils.tokens.get(parser_utils.index-1).unwrap().make_error(
+            parser_utils.file.clone(), "External functions can't have a body!".to_string()));
+    }
+
     if trait_function {
         if is_modifier(modifiers, Modifier::Internal) || is_modifier(modifiers, Modifier::Extern) {
             return Err(parser_utils.tokens.get(parser_utils.index-1).unwrap().make_error(
@@ -30,6 +30,8 @@ pub fn parse_operator(last: Option<Effects>, parser_utils: &mut ParserUtils, sta
         _ => ParseState::InOperator
     }) {
         Ok(inner) => inner.map(|inner| inner.effect),
+        // Too much nesting is still an error, anything else means the operator has no right side.
+        Err(error) if error.fatal => return Err(error),
         Err(_) => None
     };
 
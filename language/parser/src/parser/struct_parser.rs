@@ -2,12 +2,13 @@ use std::sync::Arc;
 use indexmap::IndexMap;
 use syntax::{Attribute, get_modifier, is_modifier, Modifier, ParsingError, ParsingFuture, TraitImplementor};
 use syntax::async_util::{NameResolver, UnparsedType};
-use syntax::code::{Field, MemberField};
+use syntax::code::{Effects, Field, MemberField};
 use syntax::r#struct::{get_internal, StructData, UnfinalizedStruct};
 use syntax::syntax::Syntax;
 use syntax::types::Types;
+use crate::parser::code_parser::{parse_line, ParseState};
 use crate::parser::function_parser::parse_function;
-use crate::parser::top_parser::{parse_attribute, parse_import, parse_modifier};
+use crate::parser::top_parser::{parse_attribute, parse_doc_comment, parse_import, parse_modifier};
 use crate::parser::util::ParserUtils;
 use crate::tokens::tokens::{Token, TokenTypes};
 
@@ -32,7 +33,8 @@ pub fn parse_structure(parser_utils: &mut ParserUtils, attributes: Vec<Attribute
                 parser_utils.imports.parent = Some(name.clone());
             }
             TokenTypes::GenericsStart => parse_generics(parser_utils, &mut generics),
-            TokenTypes::StructTopElement | TokenTypes::Comment => {}
+            TokenTypes::StructTopElement => {}
+            TokenTypes::Comment => parse_doc_comment(parser_utils, &token, &mut member_attributes),
             TokenTypes::InvalidCharacters => parser_utils.syntax.lock().unwrap()
                 .add_poison(Arc::new(StructData::new_poisoned(format!("{}", parser_utils.file),
                                                               token.make_error(parser_utils.file.clone(),
@@ -248,13 +250,7 @@ pub fn parse_generics(parser_utils: &mut ParserUtils, generics: &mut IndexMap<St
                 unparsed_bounds = Vec::new();
             }
             TokenTypes::GenericBound => {
-                let token = parser_utils.tokens.get(parser_utils.index-1).unwrap();
-                let mut name = token.to_string(parser_utils.buffer);
-                if name.starts_with(":") {
-                    name = name[1..].to_string();
-                }
-                let name = name.trim().to_string();
-                let unparsed = if let Some(inner) = parse_bounds(name.clone(), parser_utils) {
+                let unparsed = if let Some(inner) = parse_bound(token.to_string(parser_utils.buffer), parser_utils) {
                     inner
                 } else {
                     break;
@@ -281,6 +277,15 @@ pub fn parse_generics(parser_utils: &mut ParserUtils, generics: &mut IndexMap<St
     }
 }
 
+/// Parses a single bound token and any generics after it.
+fn parse_bound(mut name: String, parser_utils: &mut ParserUtils) -> Option<UnparsedType> {
+    // The first bound follows a :, and every other bound follows a +, like T: First + Second.
+    if name.starts_with(":") || name.starts_with("+") {
+        name = name[1..].to_string();
+    }
+    return parse_bounds(name.trim().to_string(), parser_utils);
+}
+
 pub fn parse_bounds(name: String, parser_utils: &mut ParserUtils) -> Option<UnparsedType> {
     if parser_utils.tokens[parser_utils.index].token_type == TokenTypes::GenericsStart {
         parser_utils.index += 1;
@@ -293,12 +298,7 @@ pub fn parse_bounds(name: String, parser_utils: &mut ParserUtils) -> Option<Unpa
         parser_utils.index += 1;
         match token.token_type {
             TokenTypes::Generic | TokenTypes::GenericBound => {
-                let mut name = token.to_string(parser_utils.buffer);
-                if name.starts_with(":") {
-                    name = name[1..].to_string();
-                }
-                name = name.trim().to_string();
-                let unparsed = if let Some(inner) = parse_bounds(name.clone(), parser_utils) {
+                let unparsed = if let Some(inner) = parse_bound(token.to_string(parser_utils.buffer), parser_utils) {
                     inner
                 } else {
                     break;
@@ -339,6 +339,7 @@ pub fn parse_bounds(name: String, parser_utils: &mut ParserUtils) -> Option<Unpa
 pub fn parse_field(parser_utils: &mut ParserUtils, name: String,
                    attributes: Vec<Attribute>, modifiers: Vec<Modifier>) -> ParsingFuture<MemberField> {
     let mut types = None;
+    let mut default = Ok(None);
     while !parser_utils.tokens.is_empty() {
         let token = parser_utils.tokens.get(parser_utils.index).unwrap();
         parser_utils.index += 1;
@@ -348,12 +349,29 @@ pub fn parse_field(parser_utils: &mut ParserUtils, name: String,
                 types = Some(parser_utils.get_struct(token, name))
             }
             TokenTypes::FieldSeparator => {}
+            TokenTypes::FieldValue => {
+                // The default value is the rest of the line.
+                let token = token.clone();
+                default = match parse_line(parser_utils, ParseState::None) {
+                    Ok(Some(expression)) => Ok(Some(expression.effect)),
+                    Ok(None) => Err(token.make_error(parser_utils.file.clone(), "Expected a default value!".to_string())),
+                    Err(error) => Err(error)
+                };
+                break;
+            }
             TokenTypes::FieldEnd => break,
             _ => panic!("How'd you get here? {:?}", token.token_type)
         }
     }
 
-    return Box::pin(to_field(types.unwrap(), attributes, get_modifier(modifiers.as_slice()), name));
+    return Box::pin(to_default_field(types.unwrap(), attributes, get_modifier(modifiers.as_slice()), name, default));
+}
+
+async fn to_default_field(types: ParsingFuture<Types>, attributes: Vec<Attribute>, modifier: u8, name: String,
+                          default: Result<Option<Effects>, ParsingError>) -> Result<MemberField, ParsingError> {
+    let mut field = to_field(types, attributes, modifier, name).await?;
+    field.default = default?;
+    return Ok(field);
 }
 
 pub async fn to_field(types: ParsingFuture<Types>, attributes: Vec<Attribute>, modifier: u8, name: String) -> Result<MemberField, ParsingError> {
@@ -59,6 +59,10 @@ pub fn parse_top(parser_utils: &mut ParserUtils) {
                 modifiers = Vec::new();
             }
             TokenTypes::ImplStart => {
+                // The impl token starts at the end of the last element, so point at the one after it.
+                // The message is filled in once the implementation's types are known.
+                let conflict = parser_utils.tokens.get(parser_utils.index).unwrap()
+                    .make_error(parser_utils.file.clone(), String::new());
                 let implementor = parse_implementor(parser_utils,
                                                     attributes, modifiers);
                 let process_manager = {
@@ -68,12 +72,12 @@ pub fn parse_top(parser_utils: &mut ParserUtils) {
                 };
 
                 parser_utils.handle.lock().unwrap().spawn("temp".to_string(),
-                        ParserUtils::add_implementor(parser_utils.handle.clone(), parser_utils.syntax.clone(), implementor,
+                        ParserUtils::add_implementor(parser_utils.handle.clone(), parser_utils.syntax.clone(), implementor, conflict,
                         parser_utils.imports.boxed_clone(), process_manager));
                 attributes = Vec::new();
                 modifiers = Vec::new();
             },
-            TokenTypes::Comment => {},
+            TokenTypes::Comment => parse_doc_comment(parser_utils, token, &mut attributes),
             TokenTypes::EOF => return,
             // Something went wrong when parsing, ignore till we get back on track.
             _ => {}
@@ -100,6 +104,28 @@ pub fn parse_import(parser_utils: &mut ParserUtils) {
     }
 }
 
+/// Adds a /// doc comment to the attributes of the element after it as a "doc" attribute,
+/// joining consecutive lines with newlines. Regular comments are ignored.
+pub fn parse_doc_comment(parser_utils: &ParserUtils, token: &Token, attributes: &mut Vec<Attribute>) {
+    let comment = token.to_string(parser_utils.buffer);
+    let comment = comment.trim();
+    if !comment.starts_with("///") || comment.starts_with("////") {
+        return;
+    }
+
+    let line = comment[3..].trim();
+    for attribute in attributes.iter_mut() {
+        if let Attribute::String(name, docs) = attribute {
+            if name == "doc" {
+                docs.push('\n');
+                docs.push_str(line);
+                return;
+            }
+        }
+    }
+    attributes.push(Attribute::String("doc".to_string(), line.to_string()));
+}
+
 pub fn parse_attribute(parser_utils: &mut ParserUtils, attributes: &mut Vec<Attribute>) {
     while parser_utils.index < parser_utils.tokens.len()-1 {
         let next = parser_utils.tokens.get(parser_utils.index).unwrap();
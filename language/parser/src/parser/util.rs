@@ -6,13 +6,17 @@ use syntax::{DataType, FinishedTraitImplementor, ParsingError, ParsingFuture, Pr
 use syntax::async_util::{HandleWrapper, NameResolver, UnparsedType};
 use syntax::r#struct::{StructData, UnfinalizedStruct};
 use syntax::syntax::Syntax;
-use syntax::types::Types;
+use syntax::types::{FinalizedTypes, Types};
 
 use std::sync::Mutex;
 
 use crate::{ImportNameResolver, TokenTypes};
 use crate::tokens::tokens::Token;
 
+/// How deeply lines can be nested inside each other (through parenthesis, blocks, operators, etc...) before the parser gives up.
+/// Each level recurses, so this keeps pathological input from overflowing the stack.
+pub const MAX_NESTING_DEPTH: usize = 256;
+
 pub struct ParserUtils<'a> {
     pub buffer: &'a [u8],
     pub index: usize,
@@ -21,6 +25,12 @@ pub struct ParserUtils<'a> {
     pub file: String,
     pub imports: ImportNameResolver,
     pub handle: Arc<Mutex<HandleWrapper>>,
+    // The labels continue and break jump to for each loop being parsed, innermost last.
+    pub loops: Vec<(String, String)>,
+    // How many lines are being parsed inside each other right now
+    pub depth: usize,
+    // The most lines that can be parsed inside each other, MAX_NESTING_DEPTH by default
+    pub max_depth: usize,
 }
 
 impl<'a> ParserUtils<'a> {
@@ -57,30 +67,31 @@ impl<'a> ParserUtils<'a> {
                                                              Box::new(self.imports.clone()), process_manager));
     }
 
+    /// Adds the implementation once its types are parsed, erroring at conflict if another implementation has the same types.
     pub async fn add_implementor(handle: Arc<Mutex<HandleWrapper>>, syntax: Arc<Mutex<Syntax>>, implementor: Result<TraitImplementor, ParsingError>,
-                                 resolver: Box<dyn NameResolver>, process_manager: Box<dyn ProcessManager>) {
+                                 conflict: ParsingError, resolver: Box<dyn NameResolver>, process_manager: Box<dyn ProcessManager>) {
         match implementor {
             Ok(implementor) => {
-                match Self::add_implementation(handle.clone(), syntax.clone(), implementor, resolver, process_manager).await {
+                match Self::add_implementation(handle.clone(), syntax.clone(), implementor, conflict, resolver, process_manager).await {
                     Ok(_) => {}
                     Err(error) => {
                         let mut locked = syntax.lock().unwrap();
                         locked.async_manager.parsing_impls -= 1;
-                        locked.errors.push(error);
+                        locked.add_error(error);
                     }
                 };
             }
             Err(error) => {
                 let mut locked = syntax.lock().unwrap();
                 locked.async_manager.parsing_impls -= 1;
-                locked.errors.push(error);
+                locked.add_error(error);
             }
         }
         handle.lock().unwrap().finish_task(&"temp".to_string());
     }
 
     async fn add_implementation(handle: Arc<Mutex<HandleWrapper>>, syntax: Arc<Mutex<Syntax>>, implementor: TraitImplementor,
-                                resolver: Box<dyn NameResolver>, process_manager: Box<dyn ProcessManager>) -> Result<(), ParsingError> {
+                                mut conflict: ParsingError, resolver: Box<dyn NameResolver>, process_manager: Box<dyn ProcessManager>) -> Result<(), ParsingError> {
         let mut generics = IndexMap::new();
         for (generic, bounds) in implementor.generics {
             let mut final_bounds = Vec::new();
@@ -115,6 +126,12 @@ impl<'a> ParserUtils<'a> {
 
         {
             let mut locked = syntax.lock().unwrap();
+            // Implementations of the same trait for the same type conflict, for example two implementations
+            // of Add<Point, Point> for Point would make point + point ambiguous.
+            if locked.implementations.iter().any(|other| overlaps(&locked, other, &output)) {
+                conflict.message = format!("Conflicting implementations of {} for {}", output.target, output.base);
+                locked.add_error(conflict);
+            }
             locked.implementations.push(output);
 
             locked.async_manager.parsing_impls -= 1;
@@ -153,6 +170,32 @@ impl<'a> ParserUtils<'a> {
     }
 }
 
+/// Checks if both implementations implement the same trait for a type.
+/// A blanket implementation like impl<T: Shape> Trait for T overlaps a concrete one if the concrete type meets T's bounds,
+/// ignoring the trait's generics, so impl<T> Add<T, T> for T conflicts with impl Add<Point, Point> for Point.
+/// Two implementations with generics are skipped, Chalk can't find if some type meets both of their bounds.
+fn overlaps(syntax: &Syntax, first: &FinishedTraitImplementor, second: &FinishedTraitImplementor) -> bool {
+    let (blanket, concrete) = match (first.generics.is_empty(), second.generics.is_empty()) {
+        (true, true) => return first.target == second.target && first.base == second.base,
+        (false, true) => (first, second),
+        (true, false) => (second, first),
+        (false, false) => return false
+    };
+
+    let trait_name = |implementor: &FinishedTraitImplementor|
+        implementor.target.inner_struct().data.name.split('$').next().unwrap().to_string();
+    if trait_name(blanket) != trait_name(concrete) {
+        return false;
+    }
+
+    return match (&blanket.base, &concrete.base) {
+        // Bounds with generics, like T: Iter<E>, can't be solved for the concrete type without knowing E.
+        (FinalizedTypes::Generic(name, _), FinalizedTypes::Struct(_, _)) => blanket.generics.get(name).map_or(false, |bounds|
+            bounds.iter().all(|bound| matches!(bound, FinalizedTypes::Struct(_, _)) && syntax.solve(&concrete.base, bound))),
+        _ => false
+    };
+}
+
 pub fn add_generics(input: String, parser_utils: &mut ParserUtils) -> (UnparsedType, ParsingFuture<Types>) {
     let mut generics: Vec<ParsingFuture<Types>> = Vec::new();
     let mut unparsed_generics = Vec::new();
@@ -168,11 +211,25 @@ pub fn add_generics(input: String, parser_utils: &mut ParserUtils) -> (UnparsedT
                                                          token.to_string(parser_utils.buffer),
                                                          Box::new(parser_utils.imports.clone()), vec!()))))
             }
-            TokenTypes::Operator => if let Some((unparsed, types)) = last {
-                let (unparsed, types) = inner_generic(unparsed, types, parser_utils);
-                generics.push(Box::pin(types));
-                unparsed_generics.push(unparsed);
-                last = None;
+            TokenTypes::Operator => match token.to_string(parser_utils.buffer).as_str() {
+                // Generics of a generic, like List<T>
+                "<" => if let Some((unparsed, types)) = last {
+                    let (unparsed, types) = inner_generic(unparsed, types, parser_utils);
+                    generics.push(Box::pin(types));
+                    unparsed_generics.push(unparsed);
+                    last = None;
+                },
+                ">" => {
+                    if let Some((unparsed, types)) = last {
+                        unparsed_generics.push(unparsed);
+                        generics.push(types);
+                    }
+                    break;
+                }
+                _ => {
+                    parser_utils.index -= 1;
+                    break;
+                }
             },
             TokenTypes::ArgumentEnd => if let Some((unparsed, types)) = last {
                 unparsed_generics.push(unparsed);
@@ -216,11 +273,24 @@ fn inner_generic(unparsed: UnparsedType, outer: ParsingFuture<Types>, parser_uti
                                                          token.make_error(parser_utils.file.clone(), format!("Idk here")),
                                                          token.to_string(parser_utils.buffer), Box::new(parser_utils.imports.clone()), vec!()))));
             }
-            TokenTypes::Operator => if let Some((unparsed, types)) = last {
-                let (unparsed, types) = inner_generic(unparsed, types, parser_utils);
-                unparsed_values.push(unparsed);
-                values.push(types);
-                last = None;
+            TokenTypes::Operator => match token.to_string(parser_utils.buffer).as_str() {
+                "<" => if let Some((unparsed, types)) = last {
+                    let (unparsed, types) = inner_generic(unparsed, types, parser_utils);
+                    unparsed_values.push(unparsed);
+                    values.push(types);
+                    last = None;
+                },
+                ">" => {
+                    if let Some((unparsed, types)) = last {
+                        unparsed_values.push(unparsed);
+                        values.push(types);
+                    }
+                    break;
+                }
+                _ => {
+                    parser_utils.index -= 1;
+                    break;
+                }
             },
             TokenTypes::ArgumentEnd => if let Some((unparsed, types)) = last {
                 unparsed_values.push(unparsed);
@@ -4,9 +4,14 @@ use crate::tokens::util::{parse_acceptable, parse_numbers};
 
 /// Gets the next token in a block of code.
 pub fn next_code_token(tokenizer: &mut Tokenizer) -> Token {
-    if let TokenTypes::Period = tokenizer.last.token_type && tokenizer.buffer[tokenizer.index].is_ascii_alphabetic() {
+    if let TokenTypes::Period = tokenizer.last.token_type &&
+        tokenizer.char_at(tokenizer.index).map_or(false, |(character, _)| character.is_alphabetic()) {
         parse_acceptable(tokenizer, TokenTypes::CallingType)
     } else if tokenizer.matches(";") {
+        // A field's default value is a single line, so the structure continues after it.
+        if tokenizer.state == TokenizerState::FIELD_VALUE && tokenizer.bracket_depth == 0 {
+            tokenizer.state = TokenizerState::TOP_ELEMENT_TO_STRUCT;
+        }
         tokenizer.make_token(TokenTypes::LineEnd)
     } else if tokenizer.matches("{") {
         tokenizer.bracket_depth += 1;
@@ -43,6 +48,8 @@ pub fn next_code_token(tokenizer: &mut Tokenizer) -> Token {
         tokenizer.make_token(TokenTypes::Return)
     } else if tokenizer.matches_word("break") {
         tokenizer.make_token(TokenTypes::Break)
+    } else if tokenizer.matches_word("continue") {
+        tokenizer.make_token(TokenTypes::Continue)
     } else if tokenizer.matches_word("switch") {
         tokenizer.make_token(TokenTypes::Switch)
     } else if tokenizer.matches_word("true") {
@@ -75,13 +82,23 @@ pub fn next_code_token(tokenizer: &mut Tokenizer) -> Token {
         // Changes the state type based on what the current state already is.
         tokenizer.state = if tokenizer.state == TokenizerState::CODE {
             TokenizerState::STRING
+        } else if tokenizer.state == TokenizerState::FIELD_VALUE {
+            TokenizerState::STRING_TO_FIELD_VALUE
         } else {
             TokenizerState::STRING_TO_CODE_STRUCT_TOP
         };
         tokenizer.make_token(TokenTypes::StringStart)
     } else if tokenizer.matches("'") {
-        tokenizer.index += 1;
-        if tokenizer.matches("'") {
+        // Skip to the closing quote, the parser checks if there is exactly one character or escape.
+        while tokenizer.index < tokenizer.len && tokenizer.buffer[tokenizer.index] != b'\'' &&
+            tokenizer.buffer[tokenizer.index] != b'\n' {
+            if tokenizer.buffer[tokenizer.index] == b'\\' {
+                tokenizer.index += 1;
+            }
+            tokenizer.index += 1;
+        }
+        if tokenizer.index < tokenizer.len && tokenizer.buffer[tokenizer.index] == b'\'' {
+            tokenizer.index += 1;
             tokenizer.make_token(TokenTypes::Char)
         } else {
             tokenizer.handle_invalid()
@@ -90,8 +107,10 @@ pub fn next_code_token(tokenizer: &mut Tokenizer) -> Token {
         let found = tokenizer.next_included()?;
         if tokenizer.matches("//") {
             tokenizer.parse_to_line_end(TokenTypes::Comment)
-        } else if (found as char).is_alphabetic() || found == b'_' {
-            // A character or an underscore is a variable.
+        } else if let Some((character, length)) = tokenizer.char_at(tokenizer.index - 1) &&
+            (character.is_alphabetic() || character == '_') {
+            // A letter or an underscore is a variable. Letters can be multiple bytes, so skip the rest of it.
+            tokenizer.index += length - 1;
             let temp = parse_acceptable(tokenizer, TokenTypes::Variable);
             temp
         } else if found >= b'0' && found <= b'9' {
@@ -0,0 +1,269 @@
+use crate::tokens::tokenizer::{ParserState, Tokenizer, TokenizerState};
+use crate::tokens::tokens::{Token, TokenTypes};
+
+/// A tokenized file which keeps the tokenizer's state before every token, so an edit only has to
+/// re-tokenize the tokens around it instead of the whole file. Used to keep editors in sync.
+pub struct TokenizedFile {
+    // Every token in the file, ending with an EOF token
+    pub tokens: Vec<Token>,
+    // The state of the tokenizer right before each token was parsed
+    pub states: Vec<ParserState>,
+}
+
+/// The change to a token stream after an edit.
+/// The removed tokens starting at start in the old stream were replaced by the added tokens.
+#[derive(Debug)]
+pub struct TokenDiff {
+    pub start: usize,
+    pub removed: usize,
+    pub added: Vec<Token>,
+}
+
+impl TokenizedFile {
+    pub fn new(buffer: &[u8]) -> Self {
+        let mut tokenizer = Tokenizer::new(buffer);
+        let mut tokens = Vec::new();
+        let mut states = Vec::new();
+        loop {
+            states.push(tokenizer.serialize());
+            tokens.push(tokenizer.next());
+            if tokens.last().unwrap().token_type == TokenTypes::EOF {
+                break;
+            }
+        }
+
+        return Self {
+            tokens,
+            states,
+        };
+    }
+
+    /// Checks if the file ends in the middle of something, like an unclosed function body or string.
+    /// This isn't an error while the file is still being typed, so editors can wait for more input
+    /// instead of reporting everything after the unfinished part.
+    pub fn needs_more_input(&self) -> bool {
+        // The state before the EOF token is the state the file ended in.
+        let end = self.states.last().unwrap();
+        return end.state != TokenizerState::TOP_ELEMENT || end.bracket_depth != 0;
+    }
+
+    /// Updates the tokens from the old buffer to the new buffer, returning only the tokens that changed.
+    /// Tokens after the edit are kept and moved to their new position.
+    pub fn update(&mut self, old_buffer: &[u8], new_buffer: &[u8]) -> TokenDiff {
+        let prefix = old_buffer.iter().zip(new_buffer).take_while(|(old, new)| old == new).count();
+        let suffix = old_buffer.iter().rev().zip(new_buffer.iter().rev())
+            .take(old_buffer.len().min(new_buffer.len()) - prefix)
+            .take_while(|(old, new)| old == new).count();
+        let shift = Shift::new(old_buffer, old_buffer.len() - suffix, new_buffer, new_buffer.len() - suffix);
+
+        // Start from the token before the first one touching the edit, because editing the start of a token
+        // can change the one before it (for example, removing the space between two words).
+        let start = self.tokens.iter().position(|token| token.end_offset >= prefix)
+            .unwrap_or(self.tokens.len() - 1).saturating_sub(1);
+
+        let mut tokenizer = Tokenizer::new(new_buffer);
+        tokenizer.load(&self.states[start]);
+        let mut added = Vec::new();
+        let mut added_states = Vec::new();
+        let mut synced = start;
+        loop {
+            let state = tokenizer.serialize();
+            // Past the edit, the rest of the tokens are the same if the tokenizer is in the same state it was before.
+            if state.index >= shift.new_end {
+                while synced < self.tokens.len() && (self.states[synced].index < shift.old_end ||
+                    shift.offset(self.states[synced].index) < state.index) {
+                    synced += 1;
+                }
+                if synced < self.tokens.len() && shift.same_state(&self.states[synced], &state) {
+                    break;
+                }
+            }
+
+            added_states.push(state);
+            added.push(tokenizer.next());
+            if added.last().unwrap().token_type == TokenTypes::EOF {
+                synced = self.tokens.len();
+                break;
+            }
+        }
+
+        let mut kept_tokens = self.tokens.split_off(start);
+        let mut kept_states = self.states.split_off(start);
+        let mut removed = synced - start;
+        let mut start = start;
+        self.tokens.extend(added.iter().cloned());
+        self.states.extend(added_states);
+        for token in kept_tokens.drain(removed..) {
+            self.tokens.push(shift.token(token));
+        }
+        for state in kept_states.drain(removed..) {
+            self.states.push(shift.state(state));
+        }
+
+        // Only report the tokens that actually changed.
+        while !added.is_empty() && removed > 0 && same_token(&added[0], &kept_tokens[0]) {
+            added.remove(0);
+            kept_tokens.remove(0);
+            removed -= 1;
+            start += 1;
+        }
+        while !added.is_empty() && removed > 0 && kept_tokens.last().unwrap().start_offset >= shift.old_end &&
+            same_token(added.last().unwrap(), &shift.token(kept_tokens.last().unwrap().clone())) {
+            added.pop();
+            kept_tokens.pop();
+            removed -= 1;
+        }
+
+        return TokenDiff {
+            start,
+            removed,
+            added,
+        };
+    }
+}
+
+/// Checks if two tokens are the same type at the same position.
+fn same_token(first: &Token, second: &Token) -> bool {
+    return first.token_type == second.token_type && first.start_offset == second.start_offset &&
+        first.end_offset == second.end_offset;
+}
+
+/// Moves positions after an edit to where they are in the edited buffer.
+struct Shift {
+    // The end of the edit in the old buffer
+    old_end: usize,
+    // The end of the edit in the new buffer
+    new_end: usize,
+    // The line and start of the line of the end of the edit in the old buffer
+    old_line: (u32, u32),
+    // The line and start of the line of the end of the edit in the new buffer
+    new_line: (u32, u32),
+    // The column of the end of the edit in the old buffer, in characters like token positions
+    old_column: u32,
+    // The column of the end of the edit in the new buffer
+    new_column: u32,
+}
+
+impl Shift {
+    fn new(old_buffer: &[u8], old_end: usize, new_buffer: &[u8], new_end: usize) -> Self {
+        let old_line = line_of(old_buffer, old_end);
+        let new_line = line_of(new_buffer, new_end);
+        return Self {
+            old_end,
+            new_end,
+            old_line,
+            new_line,
+            old_column: column_of(old_buffer, old_line.1 as usize, old_end),
+            new_column: column_of(new_buffer, new_line.1 as usize, new_end),
+        };
+    }
+
+    /// Moves an offset in the old buffer after the edit to the new buffer.
+    fn offset(&self, offset: usize) -> usize {
+        return offset + self.new_end - self.old_end;
+    }
+
+    /// Moves a line and the index of the start of that line.
+    fn line(&self, line: u32, line_index: u32) -> (u32, u32) {
+        return if line == self.old_line.0 {
+            self.new_line
+        } else {
+            (line + self.new_line.0 - self.old_line.0,
+             (self.offset(line_index as usize)) as u32)
+        };
+    }
+
+    /// Moves a line and column.
+    fn position(&self, position: (u32, u32)) -> (u32, u32) {
+        return if position.0 == self.old_line.0 {
+            // Positions after the edit on its last line are at or past its end, so this can't underflow.
+            (self.new_line.0, position.1 + self.new_column - self.old_column)
+        } else {
+            (position.0 + self.new_line.0 - self.old_line.0, position.1)
+        };
+    }
+
+    fn token(&self, mut token: Token) -> Token {
+        if token.start_offset >= self.old_end {
+            token.start = self.position(token.start);
+            token.start_offset = self.offset(token.start_offset);
+        }
+        token.end = self.position(token.end);
+        token.end_offset = self.offset(token.end_offset);
+        return token;
+    }
+
+    fn state(&self, mut state: ParserState) -> ParserState {
+        (state.line, state.line_index) = self.line(state.line, state.line_index);
+        state.index = self.offset(state.index);
+        state.last = self.token(state.last);
+        return state;
+    }
+
+    /// Checks if the old state, moved to the new buffer, is the same as the new state.
+    fn same_state(&self, old: &ParserState, new: &ParserState) -> bool {
+        return old.state == new.state && old.bracket_depth == new.bracket_depth &&
+            old.generic_depth == new.generic_depth && self.offset(old.index) == new.index &&
+            old.last.token_type == new.last.token_type;
+    }
+}
+
+/// Gets the line and the index of the start of the line at the offset.
+fn line_of(buffer: &[u8], offset: usize) -> (u32, u32) {
+    let line = buffer[..offset].iter().filter(|character| **character == b'\n').count() as u32 + 1;
+    let line_index = buffer[..offset].iter().rposition(|character| *character == b'\n')
+        .map(|found| found + 1).unwrap_or(0) as u32;
+    return (line, line_index);
+}
+
+/// Gets the column of the offset in characters, given the start of its line.
+fn column_of(buffer: &[u8], line_index: usize, offset: usize) -> u32 {
+    return buffer[line_index..offset].iter().filter(|byte| (**byte & 0xC0) != 0x80).count() as u32;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tokens::diff::{same_token, Shift, TokenizedFile};
+    use crate::tokens::tokens::TokenTypes;
+
+    #[test]
+    pub fn test_needs_more_input() {
+        assert!(TokenizedFile::new("fn first() {}\n\nfn second() {\n    return".as_bytes()).needs_more_input());
+        assert!(!TokenizedFile::new("fn first() {}\n".as_bytes()).needs_more_input());
+    }
+
+    #[test]
+    pub fn test_update() {
+        let old = "fn main() -> u64 {\n    let first = 1;\n    return first;\n}\n";
+        let new = "fn main() -> u64 {\n    let first = 25;\n    return first;\n}\n";
+        let mut file = TokenizedFile::new(old.as_bytes());
+        let diff = file.update(old.as_bytes(), new.as_bytes());
+
+        // Only the edited number changed, everything after it was just moved.
+        assert_eq!(diff.removed, 1, "Wrong diff: {:?}", diff);
+        assert_eq!(diff.added.len(), 1, "Wrong diff: {:?}", diff);
+        assert_eq!(diff.added[0].token_type, TokenTypes::Integer);
+
+        // The updated tokens have to match tokenizing the new buffer from scratch.
+        let fresh = TokenizedFile::new(new.as_bytes());
+        assert_eq!(file.tokens.len(), fresh.tokens.len());
+        for (updated, fresh) in file.tokens.iter().zip(&fresh.tokens) {
+            assert!(same_token(updated, fresh) && updated.start == fresh.start && updated.end == fresh.end,
+                    "Expected {:?}, found {:?}", fresh, updated);
+        }
+    }
+
+    #[test]
+    pub fn test_shift() {
+        // Inserting on a line moves everything after it on that line.
+        let shift = Shift::new("let a = 1;".as_bytes(), 5, "let abc = 1;".as_bytes(), 7);
+        assert_eq!(shift.offset(8), 10);
+        assert_eq!(shift.position((1, 8)), (1, 10));
+
+        // Inserting a line moves the lines after it down, without changing their columns.
+        let shift = Shift::new("a\nb".as_bytes(), 2, "a\n\nb".as_bytes(), 3);
+        assert_eq!(shift.offset(2), 3);
+        assert_eq!(shift.position((2, 0)), (3, 0));
+        assert_eq!(shift.line(2, 2), (3, 3));
+    }
+}
@@ -1,6 +1,7 @@
 /// This folder contains the tokenizer (also known as a Lexer)
 /// Explainer article: https://en.wikipedia.org/wiki/Lexical_analysis
 pub mod code_tokenizer;
+pub mod diff;
 pub mod tokenizer;
 pub mod tokens;
 pub mod top_tokenizer;
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use crate::tokens::code_tokenizer::next_code_token;
 use crate::tokens::tokens::{Token, TokenCodeData, TokenTypes};
 use crate::tokens::top_tokenizer::{next_func_token, next_implementation_token, next_struct_token, next_top_token};
@@ -25,7 +27,11 @@ pub struct Tokenizer<'a> {
     // A buffer of all characters in the file
     pub buffer: &'a [u8],
     // Data for token errors
-    pub code_data: Option<TokenCodeData>
+    pub code_data: Option<TokenCodeData>,
+    // The opening /* of a block comment that was never closed, if there is one
+    pub unterminated_comment: Option<Token>,
+    // The line start, index and column of the last column found, so columns don't rescan the whole line
+    column_cache: Cell<(u32, usize, u32)>
 }
 
 impl<'a> Tokenizer<'a> {
@@ -40,7 +46,9 @@ impl<'a> Tokenizer<'a> {
             last: Token::new(TokenTypes::Start, None, (1, 0), 0, (1, 0), 0),
             len: buffer.len(),
             buffer,
-            code_data: None
+            code_data: None,
+            unterminated_comment: None,
+            column_cache: Cell::new((0, 0, 0))
         };
     }
 
@@ -48,6 +56,8 @@ impl<'a> Tokenizer<'a> {
     pub fn serialize(&mut self) -> ParserState {
         return ParserState {
             state: self.state.clone(),
+            bracket_depth: self.bracket_depth.clone(),
+            generic_depth: self.generic_depth.clone(),
             index: self.index.clone(),
             line_index: self.line_index.clone(),
             line: self.line.clone(),
@@ -58,6 +68,8 @@ impl<'a> Tokenizer<'a> {
     /// Loads the state from a ParserState
     pub fn load(&mut self, state: &ParserState) {
         self.state = state.state.clone();
+        self.bracket_depth = state.bracket_depth.clone();
+        self.generic_depth = state.generic_depth.clone();
         self.index = state.index.clone();
         self.line_index = state.line_index.clone();
         self.line = state.line.clone();
@@ -65,14 +77,15 @@ impl<'a> Tokenizer<'a> {
     }
 
     pub fn next(&mut self) -> Token {
-        if self.matches("//") {
+        // Strings can contain anything, including comment starts.
+        let in_string = self.state == TokenizerState::STRING || self.state == TokenizerState::STRING_TO_CODE_STRUCT_TOP ||
+            self.state == TokenizerState::STRING_TO_FIELD_VALUE;
+        if !in_string && self.matches("//") {
             self.parse_to_line_end(TokenTypes::Comment);
             self.last = self.make_token(TokenTypes::Comment);
             return self.last.clone();
-        } else if self.matches("/*") {
-            while !self.matches("*/") {
-                self.index += 1;
-            }
+        } else if !in_string && self.matches("/*") {
+            self.parse_block_comment();
             self.last = self.make_token(TokenTypes::Comment);
             return self.last.clone();
         }
@@ -82,8 +95,9 @@ impl<'a> Tokenizer<'a> {
             TokenizerState::FUNCTION | TokenizerState::FUNCTION_TO_STRUCT_TOP => next_func_token(self),
             TokenizerState::STRUCTURE => next_struct_token(self),
             TokenizerState::IMPLEMENTATION => next_implementation_token(self),
-            TokenizerState::STRING | TokenizerState::STRING_TO_CODE_STRUCT_TOP => parse_string(self),
-            TokenizerState::CODE | TokenizerState::CODE_TO_STRUCT_TOP => next_code_token(self),
+            TokenizerState::STRING | TokenizerState::STRING_TO_CODE_STRUCT_TOP |
+            TokenizerState::STRING_TO_FIELD_VALUE => parse_string(self),
+            TokenizerState::CODE | TokenizerState::CODE_TO_STRUCT_TOP | TokenizerState::FIELD_VALUE => next_code_token(self),
             TokenizerState::GENERIC_TO_IMPL | TokenizerState::GENERIC_TO_FUNC |
             TokenizerState::GENERIC_TO_STRUCT | TokenizerState::GENERIC_TO_FUNC_TO_STRUCT_TOP => next_generic(self),
             _ => panic!("Unknown state {}!", self.state)
@@ -97,7 +111,7 @@ impl<'a> Tokenizer<'a> {
         loop {
             if self.index == self.len {
                 return Err(Token::new(TokenTypes::EOF, None, self.last.end, self.last.end_offset,
-                                      (self.line, self.index as u32 - self.line_index), self.index));
+                                      (self.line, self.column()), self.index));
             }
             let character = self.buffer[self.index];
             self.index += 1;
@@ -137,7 +151,8 @@ impl<'a> Tokenizer<'a> {
                 return false;
             }
         }
-        return if !self.buffer[self.index].is_ascii_alphabetic() {
+        // The end of the file also ends the word.
+        return if !self.buffer.get(self.index).is_some_and(|found| found.is_ascii_alphabetic()) {
             true
         } else {
             self.load(&state);
@@ -145,21 +160,52 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    /// Parses past the end of a block comment, after the opening /* has been matched.
+    /// Block comments can be nested, so each /* must have a matching */.
+    /// Ex: /* a /* b */ c */ is one comment.
+    fn parse_block_comment(&mut self) {
+        let opening = Token::new(TokenTypes::Comment, None, (self.line, self.column() - 2),
+                                 self.index - 2, (self.line, self.column()), self.index);
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next_included() {
+                Ok(b'*') if self.index < self.len && self.buffer[self.index] == b'/' => {
+                    self.index += 1;
+                    depth -= 1;
+                }
+                Ok(b'/') if self.index < self.len && self.buffer[self.index] == b'*' => {
+                    self.index += 1;
+                    depth += 1;
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    self.unterminated_comment = Some(opening);
+                    return;
+                }
+            }
+        }
+    }
+
     /// Parse ahead to the first occurrence of whichever token occurs first
     pub fn parse_to_first(&mut self, token: TokenTypes, first: u8, second: u8) -> Token {
         while self.index != self.len && self.buffer[self.index] != first && self.buffer[self.index] != second {
             self.index += 1;
+            // Newlines can be skipped over here, so they still have to be counted.
+            if self.buffer[self.index - 1] == b'\n' {
+                self.line_index = self.index as u32;
+                self.line += 1;
+            }
         }
 
         return Token::new(token, self.code_data.clone(), self.last.end, self.last.end_offset,
-                          (self.line, self.index as u32 - self.line_index), self.index);
+                          (self.line, self.column()), self.index);
     }
 
     /// Parse ahead to the end of the current line
     pub fn parse_to_line_end(&mut self, types: TokenTypes) -> Token {
         if self.index == self.len {
             return Token::new(TokenTypes::EOF, self.code_data.clone(), self.last.end, self.last.end_offset,
-                              (self.line, self.index as u32 - self.line_index), self.index);
+                              (self.line, self.column()), self.index);
         }
 
         loop {
@@ -170,7 +216,7 @@ impl<'a> Tokenizer<'a> {
         }
 
         return Token::new(types, self.code_data.clone(), self.last.end, self.last.end_offset,
-                          (self.line, self.index as u32 - self.line_index), self.index - 1);
+                          (self.line, self.column()), self.index - 1);
     }
 
     /// Creates an InvalidCharacters token, used for debugging (you can put a breakpoint here)
@@ -178,16 +224,53 @@ impl<'a> Tokenizer<'a> {
         return self.parse_to_line_end(TokenTypes::InvalidCharacters);
     }
 
+    /// Decodes the UTF-8 character starting at the index, returning it and its length in bytes.
+    /// Returns None at the end of the file or if the bytes aren't valid UTF-8.
+    pub fn char_at(&self, index: usize) -> Option<(char, usize)> {
+        let length = match *self.buffer.get(index)? {
+            byte if byte < 0x80 => 1,
+            byte if byte >= 0xF0 => 4,
+            byte if byte >= 0xE0 => 3,
+            _ => 2
+        };
+        let character = std::str::from_utf8(self.buffer.get(index..index + length)?).ok()?.chars().next()?;
+        return Some((character, length));
+    }
+
+    /// The column of the current index in its line. Counted in characters instead of bytes,
+    /// so multi-byte UTF-8 characters don't push errors to the right.
+    /// Counts from the last column found on the same line, so tokenizing a line stays linear in its length.
+    pub fn column(&self) -> u32 {
+        let (line_index, index, column) = self.column_cache.get();
+        let column = if line_index != self.line_index || index < self.line_index as usize {
+            count_chars(&self.buffer[self.line_index as usize..self.index])
+        } else if index <= self.index {
+            column + count_chars(&self.buffer[index..self.index])
+        } else {
+            column - count_chars(&self.buffer[self.index..index])
+        };
+        self.column_cache.set((self.line_index, self.index, column));
+        return column;
+    }
+
     /// Creates a token between the last token and the current position
     pub fn make_token(&self, token_type: TokenTypes) -> Token {
         return Token::new(token_type, self.code_data.clone(), self.last.end, self.last.end_offset,
-                          (self.line, self.index as u32 - self.line_index), self.index);
+                          (self.line, self.column()), self.index);
     }
 }
 
+/// Counts the characters in UTF-8 bytes by skipping continuation bytes.
+fn count_chars(bytes: &[u8]) -> u32 {
+    return bytes.iter().filter(|byte| (**byte & 0xC0) != 0x80).count() as u32;
+}
+
 /// A serialized parser state, used to save/load the state of parsing mid-file.
+#[derive(Clone, Debug)]
 pub struct ParserState {
     pub state: u64,
+    pub bracket_depth: u8,
+    pub generic_depth: u8,
     pub index: usize,
     pub line_index: u32,
     pub line: u32,
@@ -229,4 +312,8 @@ impl TokenizerState {
     pub const CODE: u64 = 0xC;
     // A block of code that returns to a structure
     pub const CODE_TO_STRUCT_TOP: u64 = 0xD;
+    // The default value of a structure's field, which returns to the structure at the end of the line
+    pub const FIELD_VALUE: u64 = 0xE;
+    // A string inside a field's default value
+    pub const STRING_TO_FIELD_VALUE: u64 = 0xF;
 }
\ No newline at end of file
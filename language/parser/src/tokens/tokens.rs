@@ -166,5 +166,6 @@ pub enum TokenTypes {
     GenericsEnd = 68,
     Do = 69,
     Char = 70,
-    BlankLine = 71
+    BlankLine = 71,
+    Continue = 72
 }
\ No newline at end of file
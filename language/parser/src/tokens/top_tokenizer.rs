@@ -86,7 +86,7 @@ pub fn next_top_token(tokenizer: &mut Tokenizer) -> Token {
         TokenTypes::FieldType => if tokenizer.matches("=") {
             // Handles the code for the field's value
             if tokenizer.state == TokenizerState::TOP_ELEMENT_TO_STRUCT {
-                tokenizer.state = TokenizerState::CODE_TO_STRUCT_TOP;
+                tokenizer.state = TokenizerState::FIELD_VALUE;
             } else {
                 tokenizer.state = TokenizerState::CODE;
             }
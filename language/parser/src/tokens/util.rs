@@ -34,19 +34,32 @@ pub fn parse_acceptable(tokenizer: &mut Tokenizer, token_type: TokenTypes) -> To
         if tokenizer.index == tokenizer.len {
             return tokenizer.make_token(TokenTypes::EOF);
         }
-        let character = tokenizer.buffer[tokenizer.index] as char;
+        // Identifiers can have any Unicode letter, so decode the whole character instead of one byte.
+        let (character, length) = tokenizer.char_at(tokenizer.index).unwrap_or((' ', 1));
         if !character.is_alphanumeric() && character != ':' && character != '_' {
             if tokenizer.buffer[tokenizer.index - 1] == b':' {
                 tokenizer.index -= 1;
             }
             return tokenizer.make_token(token_type);
         }
-        tokenizer.index += 1;
+        tokenizer.index += length;
     }
 }
 
 /// Parses numbers
 pub fn parse_numbers(tokenizer: &mut Tokenizer) -> Token {
+    // Numbers starting with 0x, 0b, or 0o are hexadecimal, binary, or octal integers.
+    // The parser checks the digits are valid for the base.
+    if tokenizer.buffer[tokenizer.index - 1] == b'0' && tokenizer.index < tokenizer.len &&
+        [b'x', b'b', b'o'].contains(&tokenizer.buffer[tokenizer.index]) {
+        tokenizer.index += 1;
+        while tokenizer.index < tokenizer.len && (tokenizer.buffer[tokenizer.index].is_ascii_alphanumeric() ||
+            tokenizer.buffer[tokenizer.index] == b'_') {
+            tokenizer.index += 1;
+        }
+        return tokenizer.make_token(TokenTypes::Integer);
+    }
+
     let mut float = false;
 
     loop {
@@ -66,8 +79,21 @@ pub fn parse_numbers(tokenizer: &mut Tokenizer) -> Token {
             } else {
                 float = true;
             }
+        } else if (character == 'e' || character == 'E') && tokenizer.buffer[tokenizer.index - 1].is_ascii_digit() {
+            // An exponent, like 1.5e10 or 2e-3, is always a float. The parser errors if it has no digits.
+            tokenizer.index += 1;
+            if tokenizer.index < tokenizer.len && (tokenizer.buffer[tokenizer.index] == b'+' ||
+                tokenizer.buffer[tokenizer.index] == b'-') {
+                tokenizer.index += 1;
+            }
+            while tokenizer.index < tokenizer.len && (tokenizer.buffer[tokenizer.index].is_ascii_digit() ||
+                tokenizer.buffer[tokenizer.index] == b'_') {
+                tokenizer.index += 1;
+            }
+            return tokenizer.make_token(TokenTypes::Float);
         } else {
-            if !character.is_numeric() {
+            // Underscores separate digits, the parser checks they're between two digits.
+            if !character.is_numeric() && character != '_' {
                 return if float {
                     // If no number is after the period assume it's a method call not a float.
                     if tokenizer.buffer[tokenizer.index-1] == b'.' {
@@ -111,6 +137,8 @@ pub fn parse_string(tokenizer: &mut Tokenizer) -> Token {
             b'"' => return if /*tokenizer.last.token_type != TokenTypes::StringEscape*/tokenizer.buffer[tokenizer.index - 1] != '\\' as u8 {
                 tokenizer.state = if tokenizer.state == TokenizerState::STRING_TO_CODE_STRUCT_TOP {
                     TokenizerState::CODE_TO_STRUCT_TOP
+                } else if tokenizer.state == TokenizerState::STRING_TO_FIELD_VALUE {
+                    TokenizerState::FIELD_VALUE
                 } else {
                     TokenizerState::CODE
                 };
@@ -120,10 +148,22 @@ pub fn parse_string(tokenizer: &mut Tokenizer) -> Token {
             },
             b'\\' => {
 
-                // if it is a hex value, then increment the tokenizer by an extra 2 because
-                // the escape character is 4 characters long instead of 2 (ex. \xAA)
-                if tokenizer.buffer[tokenizer.index] == b'x' {
-                    tokenizer.index += 2;
+                match tokenizer.buffer.get(tokenizer.index) {
+                    // if it is a hex value, then increment the tokenizer by an extra 2 because
+                    // the escape character is 4 characters long instead of 2 (ex. \xAA)
+                    Some(b'x') => tokenizer.index = (tokenizer.index + 2).min(tokenizer.len - 1),
+                    // if it is a unicode value, then skip to the closing brace (ex. \u{1F600}).
+                    // if there is no closing brace, only the \u is included so the parser can error.
+                    Some(b'u') if tokenizer.buffer.get(tokenizer.index + 1) == Some(&b'{') => {
+                        let mut end = tokenizer.index + 2;
+                        while end < tokenizer.len && tokenizer.buffer[end] != b'}' && tokenizer.buffer[end] != b'"' {
+                            end += 1;
+                        }
+                        if end < tokenizer.len && tokenizer.buffer[end] == b'}' {
+                            tokenizer.index = end;
+                        }
+                    }
+                    _ => {}
                 }
 
                 // increment the tokenizer so that it includes the \
@@ -133,6 +173,11 @@ pub fn parse_string(tokenizer: &mut Tokenizer) -> Token {
 
                 return tokenizer.make_token(TokenTypes::StringEscape)
             },
+            // strings can span multiple lines, so keep the line count right for later tokens
+            b'\n' => {
+                tokenizer.line_index = tokenizer.index as u32;
+                tokenizer.line += 1;
+            }
             _ => {}
         }
     }
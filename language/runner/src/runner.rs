@@ -1,23 +1,44 @@
+use std::future::Future;
 use std::sync::Arc;
 
 use anyhow::Error;
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::time;
 
 use checker::output::TypesChecker;
-use data::{Arguments, CompilerArguments};
+use data::{Arguments, CompilerArguments, Timings};
 use parser::parse;
+use parser::parser::util::MAX_NESTING_DEPTH;
 use syntax::async_util::HandleWrapper;
 use syntax::ParsingError;
-use syntax::syntax::Syntax;
+use syntax::syntax::{Compiler, Syntax};
 
 use crate::{get_compiler, JoinWaiter};
 
 pub async fn run<T: Send + 'static>(settings: &Arguments)
                                     -> Result<Option<T>, Vec<ParsingError>> {
+    return compile_with(settings, start::<T>).await;
+}
+
+/// Compiles the target like run, but returns the module's IR instead of running it.
+pub async fn emit_ir(settings: &Arguments) -> Result<String, Vec<ParsingError>> {
+    // start_ir always sends its result, so there is always an output.
+    return compile_with(settings, start_ir).await?.unwrap();
+}
+
+/// Parses and verifies the sources like run, but returns the syntax instead of compiling it.
+pub async fn check(settings: &Arguments) -> Result<Arc<Mutex<Syntax>>, Vec<ParsingError>> {
+    // start_check always sends the syntax, so there is always an output.
+    return Ok(compile_with(settings, start_check).await?.unwrap());
+}
+
+/// Parses and verifies the sources, then lets the compiler started by the starter finish and returns its output.
+async fn compile_with<T: Send + 'static, F: Future<Output=()> + Send + 'static>(
+    settings: &Arguments, starter: impl FnOnce(CompilerArguments, Sender<Option<T>>, Receiver<()>, Arc<Mutex<Syntax>>) -> F)
+    -> Result<Option<T>, Vec<ParsingError>> {
     //Parse source, getting handles and building into the unresolved syntax.
     let handle = Arc::new(Mutex::new(HandleWrapper {
         handle: settings.cpu_runtime.handle().clone(),
@@ -28,13 +49,17 @@ pub async fn run<T: Send + 'static>(settings: &Arguments)
     let mut syntax = Syntax::new(Box::new(
         TypesChecker::new(handle.clone(), settings.runner_settings.include_references())));
     syntax.async_manager.target = settings.runner_settings.compiler_arguments.target.clone();
+    syntax.timings = settings.runner_settings.timings.clone();
+    let compiler_arguments = &settings.runner_settings.compiler_arguments;
+    syntax.set_solver_limits(compiler_arguments.solver_overflow_depth.unwrap_or(syntax.solver_overflow_depth),
+                             compiler_arguments.solver_max_size.unwrap_or(syntax.solver_max_size));
 
     let syntax = Arc::new(Mutex::new(syntax));
 
     let (sender, mut receiver) = mpsc::channel(1);
     let (go_sender, go_receiver) = mpsc::channel(1);
 
-    settings.cpu_runtime.spawn(start(settings.runner_settings.compiler_arguments.clone(), sender, go_receiver, syntax.clone()));
+    settings.cpu_runtime.spawn(starter(settings.runner_settings.compiler_arguments.clone(), sender, go_receiver, syntax.clone()));
 
     let mut handles = Vec::new();
     for source_set in &settings.runner_settings.sources {
@@ -47,7 +72,7 @@ pub async fn run<T: Send + 'static>(settings: &Arguments)
                 settings.io_runtime.as_ref().map(|inner| inner.handle().clone()).unwrap_or(settings.cpu_runtime.handle().clone())
                     .spawn(parse(syntax.clone(), handle.clone(),
                                  source_set.relative(&file).clone(),
-                                 file.read())));
+                                 file.read(), settings.runner_settings.compiler_arguments.max_nesting.unwrap_or(MAX_NESTING_DEPTH))));
         }
     }
 
@@ -69,6 +94,7 @@ pub async fn run<T: Send + 'static>(settings: &Arguments)
         panic!("Error detected!");
     }
 
+    let start_time = Instant::now();
     syntax.lock().unwrap().finish();
 
     match time::timeout(Duration::from_secs(30), JoinWaiter { handle: handle.clone() }).await {
@@ -81,10 +107,24 @@ pub async fn run<T: Send + 'static>(settings: &Arguments)
         }
     }
 
+    Timings::record(&settings.runner_settings.timings, "verify", start_time);
+
+    for warning in &syntax.lock().unwrap().warnings {
+        println!("Warning: {}", warning);
+    }
+
     let errors = syntax.lock().unwrap().errors.clone();
     return if errors.is_empty() {
+        // The compiler records its own timing, since running the code isn't part of compiling.
         go_sender.send(()).await.unwrap();
-        Ok(receiver.recv().await.unwrap())
+        let output = receiver.recv().await.unwrap();
+        // The compiler adds errors if the compiled code fails to verify.
+        let errors = syntax.lock().unwrap().errors.clone();
+        if errors.is_empty() {
+            Ok(output)
+        } else {
+            Err(errors)
+        }
     } else {
         Err(errors)
     }
@@ -99,4 +139,23 @@ pub async fn start<T>(compiler_arguments: CompilerArguments, sender: Sender<Opti
     }
 
     let _ = sender.send(code_compiler.compile(receiver, &syntax).await).await;
-}
\ No newline at end of file
+}
+
+pub async fn start_ir(compiler_arguments: CompilerArguments, sender: Sender<Option<Result<String, Vec<ParsingError>>>>,
+                      receiver: Receiver<()>, syntax: Arc<Mutex<Syntax>>) {
+    let code_compiler: Box<dyn Compiler<()> + Send + Sync>;
+    {
+        let locked = syntax.lock().unwrap();
+        code_compiler = get_compiler(locked.compiling.clone(),
+                                     locked.strut_compiling.clone(), compiler_arguments);
+    }
+
+    let _ = sender.send(Some(code_compiler.emit_ir(receiver, &syntax).await)).await;
+}
+
+pub async fn start_check(_compiler_arguments: CompilerArguments, sender: Sender<Option<Arc<Mutex<Syntax>>>>,
+                         mut receiver: Receiver<()>, syntax: Arc<Mutex<Syntax>>) {
+    // Nothing is compiled, so the syntax is sent once it's verified.
+    receiver.recv().await;
+    let _ = sender.send(Some(syntax)).await;
+}
@@ -34,24 +34,13 @@ pub struct AsyncDataGetter<T: TopElement> {
 }
 
 impl<T: TopElement> AsyncTypesGetter<T> {
-    /// Helper method to try a get a type with the given prefix, and adding a waker if not.
-    fn get_types(&mut self, locked: &mut Syntax, prefix: String, waker: Waker, not_trait: bool)
-                 -> Option<Result<Arc<T>, ParsingError>> {
-        // Add the prefix to the name, if any.
-        let name = if prefix.is_empty() {
-            self.getting.clone()
-        } else if prefix.ends_with(&self.getting) {
-            prefix
-        } else {
-            prefix + "::" + &*self.getting.clone()
-        };
-
+    /// Helper method to try a get a type with the given fully-qualified name, and adding a waker if not.
+    fn get_types(&mut self, locked: &mut Syntax, name: String, waker: Waker, not_trait: bool) -> Option<Arc<T>> {
         let getting = T::get_manager(locked);
         //Look for a structure of that name
         if let Some(found) = getting.types.get(&name).cloned() {
             if !not_trait || !found.is_trait() {
-                self.finished = Some(found.clone());
-                return Some(Ok(found));
+                return Some(found);
             }
         }
 
@@ -121,22 +110,54 @@ impl<T: TopElement> Future for AsyncTypesGetter<T> {
         let locked = self.syntax.clone();
         let mut locked = locked.lock().unwrap();
 
-        // Check if an element directly referenced with that name exists.
-        if let Some(output) = self.get_types(&mut locked,
-                                             String::new(), cx.waker().clone(), not_trait) {
-            self.clean_up(&mut locked, self.name_resolver.imports());
-            return Poll::Ready(output);
+        let path: Vec<String> = self.getting.split("::").map(|part| part.to_string()).collect();
+        let mut candidates = self.name_resolver.resolve_path(&path);
+        let qualified = candidates.pop().unwrap();
+
+        // The file's own module is the first import, and its elements shadow anything imported.
+        if !candidates.is_empty() {
+            let own = candidates.remove(0);
+            if let Some(output) = self.get_types(&mut locked, own, cx.waker().clone(), not_trait) {
+                self.finished = Some(output.clone());
+                self.clean_up(&mut locked, self.name_resolver.imports());
+                return Poll::Ready(Ok(output));
+            }
         }
 
         // Check each import if the element is in those files.
-        for import in self.name_resolver.imports().clone() {
-            if let Some(output) = self.get_types(&mut locked,
-                                                 import, cx.waker().clone(), not_trait) {
-                self.clean_up(&mut locked, self.name_resolver.imports());
-                return Poll::Ready(output);
+        let mut found: Vec<Arc<T>> = Vec::new();
+        for candidate in &candidates {
+            if let Some(output) = self.get_types(&mut locked, candidate.clone(), cx.waker().clone(), not_trait) {
+                if !found.iter().any(|other| other.name() == output.name()) {
+                    found.push(output);
+                }
             }
         }
 
+        if found.len() > 1 {
+            let mut error = self.error.clone();
+            error.message = format!("{} is ambiguous, it could be any of: {}", self.getting,
+                                    found.iter().map(|found| found.name().clone()).collect::<Vec<_>>().join(", "));
+            return Poll::Ready(Err(error));
+        }
+
+        // Another import could still add an element with the same name until parsing is finished,
+        // so with more than one import to look in it's only unambiguous once everything is parsed.
+        if found.len() == 1 && candidates.len() > 1 && !locked.async_manager.finished {
+            return Poll::Pending;
+        }
+
+        // Fall back to an element directly referenced with that name.
+        let output = match found.pop() {
+            Some(output) => Some(output),
+            None => self.get_types(&mut locked, qualified, cx.waker().clone(), not_trait)
+        };
+        if let Some(output) = output {
+            self.finished = Some(output.clone());
+            self.clean_up(&mut locked, self.name_resolver.imports());
+            return Poll::Ready(Ok(output));
+        }
+
         // If the async manager is finished, return an error.
         if locked.async_manager.finished {
             return Poll::Ready(Err(self.error.clone()));
@@ -204,6 +225,25 @@ pub trait NameResolver: Send + Sync {
 
     fn generics(&self) -> &HashMap<String, Vec<UnparsedType>>;
 
+    /// Gets every fully-qualified name the path could refer to, one for each import in order followed by the path itself.
+    fn resolve_path(&self, path: &[String]) -> Vec<String> {
+        let joined = path.join("::");
+        let mut output = Vec::new();
+        for import in self.imports() {
+            // Imports of the element itself, like math::Add, only match whole names so SaturatingAdd isn't Add.
+            let name = if import == &joined || import.ends_with(&format!("::{}", joined)) {
+                import.clone()
+            } else {
+                format!("{}::{}", import, joined)
+            };
+            if !output.contains(&name) {
+                output.push(name);
+            }
+        }
+        output.push(joined);
+        return output;
+    }
+
     /// Clones the name resolver in a box, because it's a trait it can't be directly cloned.
     fn boxed_clone(&self) -> Box<dyn NameResolver>;
 }
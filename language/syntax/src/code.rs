@@ -54,6 +54,8 @@ pub struct MemberField {
     pub modifiers: u8,
     pub attributes: Vec<Attribute>,
     pub field: Field,
+    // The value used when a struct is created without this field, if there is one.
+    pub default: Option<Effects>,
 }
 
 /// A finalized member field.
@@ -62,6 +64,8 @@ pub struct FinalizedMemberField {
     pub modifiers: u8,
     pub attributes: Vec<Attribute>,
     pub field: FinalizedField,
+    // The default value, checked once with the structure's imports.
+    pub default: Option<FinalizedEffects>,
 }
 
 impl MemberField {
@@ -70,6 +74,7 @@ impl MemberField {
             modifiers,
             attributes,
             field,
+            default: None,
         };
     }
 }
@@ -259,6 +264,46 @@ impl FinalizedEffects {
         return temp;
     }
 
+    /// Adds the name of every function this effect calls to the output, including calls nested inside it.
+    /// Virtual calls are resolved at runtime, so they add the trait's function instead of the implementation.
+    pub fn calls(&self, output: &mut Vec<String>) {
+        match self {
+            FinalizedEffects::MethodCall(calling, function, arguments) => {
+                if let Some(calling) = calling {
+                    calling.calls(output);
+                }
+                output.push(function.data.name.clone());
+                arguments.iter().for_each(|argument| argument.calls(output));
+            }
+            FinalizedEffects::GenericMethodCall(function, _, arguments) |
+            FinalizedEffects::VirtualCall(_, function, arguments) |
+            FinalizedEffects::GenericVirtualCall(_, _, function, arguments) => {
+                output.push(function.data.name.clone());
+                arguments.iter().for_each(|argument| argument.calls(output));
+            }
+            FinalizedEffects::CodeBody(body) =>
+                body.expressions.iter().for_each(|expression| expression.effect.calls(output)),
+            FinalizedEffects::CreateVariable(_, inner, _) | FinalizedEffects::CompareJump(inner, _, _) |
+            FinalizedEffects::Load(inner, _, _) | FinalizedEffects::Downcast(inner, _) |
+            FinalizedEffects::HeapStore(inner) | FinalizedEffects::ReferenceLoad(inner) |
+            FinalizedEffects::StackStore(inner) => inner.calls(output),
+            FinalizedEffects::Set(target, value) => {
+                target.calls(output);
+                value.calls(output);
+            }
+            FinalizedEffects::CreateStruct(target, _, fields) => {
+                if let Some(target) = target {
+                    target.calls(output);
+                }
+                fields.iter().for_each(|(_, field)| field.calls(output));
+            }
+            FinalizedEffects::CreateArray(_, values) => values.iter().for_each(|value| value.calls(output)),
+            FinalizedEffects::NOP() | FinalizedEffects::Jump(_) | FinalizedEffects::LoadVariable(_) |
+            FinalizedEffects::HeapAllocate(_) | FinalizedEffects::Float(_) | FinalizedEffects::UInt(_) |
+            FinalizedEffects::Bool(_) | FinalizedEffects::String(_) | FinalizedEffects::Char(_) => {}
+        }
+    }
+
     /// Degenericing replaces every instance of a generic function with its actual type.
     /// This mostly targets FinalizedTypes or function calls and calls the degeneric function on them.
     #[async_recursion]
@@ -441,13 +486,8 @@ pub async fn degeneric_header(degenericed: Arc<FunctionData>, base: Arc<Function
     locked.functions.wakers.remove(&new_method.data.name);
 
     // Give the compiler the empty body
-    locked.compiling.write().unwrap().insert(new_method.data.name.clone(),
-                                             Arc::new(CodelessFinalizedFunction::clone(&new_method).add_code(
-                                                 FinalizedCodeBody::new(vec!(), "empty".to_string(), true))));
-    for waker in &locked.compiling_wakers {
-        waker.wake_by_ref();
-    }
-    locked.compiling_wakers.clear();
+    locked.add_compiling(Arc::new(CodelessFinalizedFunction::clone(&new_method).add_code(
+        FinalizedCodeBody::new(vec!(), "empty".to_string(), true))));
     return Ok(());
 }
 
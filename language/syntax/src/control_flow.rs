@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+/// This file contains a control flow graph of a function's code, made of basic blocks and the edges between them.
+/// The blocks match the blocks the compiler outputs, so it can be used for analysis before compiling.
+use indexmap::IndexMap;
+
+use crate::code::{ExpressionType, FinalizedEffects, FinalizedExpression};
+use crate::function::FinalizedCodeBody;
+
+/// The control flow graph of a body of code.
+#[derive(Clone, Debug)]
+pub struct ControlFlowGraph {
+    // The label of the first block
+    pub entry: String,
+    // Every block in the code, in the order they appear
+    pub blocks: IndexMap<String, BasicBlock>,
+}
+
+/// A block of code with no control flow inside it, ending in a terminator.
+#[derive(Clone, Debug)]
+pub struct BasicBlock {
+    pub label: String,
+    // Every expression in the block before the terminator
+    pub expressions: Vec<FinalizedExpression>,
+    pub terminator: Terminator,
+}
+
+/// How a basic block exits.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Terminator {
+    // Jumps to the given label
+    Jump(String),
+    // Jumps to the first label if the comparison is true, or the second if false
+    Branch(String, String),
+    // Returns from the function
+    Return,
+    // The block falls off the end without a terminator
+    None,
+}
+
+impl BasicBlock {
+    pub fn new(label: String) -> Self {
+        return Self {
+            label,
+            expressions: Vec::new(),
+            terminator: Terminator::None,
+        };
+    }
+
+    /// The labels this block can jump to.
+    pub fn successors(&self) -> Vec<&String> {
+        return match &self.terminator {
+            Terminator::Jump(label) => vec!(label),
+            Terminator::Branch(first, second) => vec!(first, second),
+            Terminator::Return | Terminator::None => vec!()
+        };
+    }
+}
+
+impl ControlFlowGraph {
+    /// Builds the graph of the code, splitting it on every jump, comparison, return, or nested body.
+    pub fn new(code: &FinalizedCodeBody) -> Self {
+        let mut graph = Self {
+            entry: code.label.clone(),
+            blocks: IndexMap::new(),
+        };
+        graph.add_body(code);
+        return graph;
+    }
+
+    /// Every edge in the graph, from a block to the block it jumps to.
+    pub fn edges(&self) -> Vec<(&String, &String)> {
+        let mut output = Vec::new();
+        for block in self.blocks.values() {
+            for successor in block.successors() {
+                output.push((&block.label, successor));
+            }
+        }
+        return output;
+    }
+
+    /// Every block that can't be reached from the entry block.
+    pub fn unreachable(&self) -> Vec<&String> {
+        let mut reached = HashSet::new();
+        let mut checking = vec!(&self.entry);
+        while let Some(label) = checking.pop() {
+            if !reached.insert(label) {
+                continue;
+            }
+            if let Some(block) = self.blocks.get(label) {
+                checking.extend(block.successors());
+            }
+        }
+
+        return self.blocks.keys().filter(|label| !reached.contains(label)).collect();
+    }
+
+    /// Adds the blocks of a body, following the same rules as the compiler: nested bodies are jumped to,
+    /// and code after a nested body that doesn't return continues in the body's end block.
+    fn add_body(&mut self, code: &FinalizedCodeBody) {
+        let mut current = code.label.clone();
+        self.blocks.entry(current.clone()).or_insert_with(|| BasicBlock::new(code.label.clone()));
+        let mut broke = false;
+        for line in &code.expressions {
+            if let FinalizedEffects::CodeBody(body) = &line.effect {
+                if !broke {
+                    self.terminate(&current, Terminator::Jump(body.label.clone()));
+                }
+                self.add_body(body);
+
+                if broke || body.returns || line.expression_type != ExpressionType::Line {
+                    broke = true;
+                } else {
+                    current = body.label.clone() + "end";
+                    self.blocks.entry(current.clone()).or_insert_with(|| BasicBlock::new(body.label.clone() + "end"));
+                }
+                continue;
+            }
+
+            // Anything after a terminator is unreachable, so it isn't part of any block.
+            if broke {
+                continue;
+            }
+
+            match (&line.expression_type, &line.effect) {
+                (ExpressionType::Line, FinalizedEffects::Jump(label)) =>
+                    self.terminate(&current, Terminator::Jump(label.clone())),
+                (ExpressionType::Line, FinalizedEffects::CompareJump(_, first, second)) =>
+                    self.terminate(&current, Terminator::Branch(first.clone(), second.clone())),
+                (ExpressionType::Line, _) => {
+                    self.blocks.get_mut(&current).unwrap().expressions.push(line.clone());
+                    continue;
+                }
+                // A break gives its value to the code around the block, so the block ends without a terminator.
+                (ExpressionType::Break, _) =>
+                    self.blocks.get_mut(&current).unwrap().expressions.push(line.clone()),
+                (ExpressionType::Return, _) => {
+                    self.blocks.get_mut(&current).unwrap().expressions.push(line.clone());
+                    self.terminate(&current, Terminator::Return);
+                }
+            }
+            broke = true;
+        }
+    }
+
+    fn terminate(&mut self, label: &String, terminator: Terminator) {
+        self.blocks.get_mut(label).unwrap().terminator = terminator;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::code::{ExpressionType, FinalizedEffects, FinalizedExpression};
+    use crate::control_flow::{ControlFlowGraph, Terminator};
+    use crate::function::FinalizedCodeBody;
+
+    fn line(effect: FinalizedEffects) -> FinalizedExpression {
+        return FinalizedExpression::new(ExpressionType::Line, effect);
+    }
+
+    fn body(label: &str, expressions: Vec<FinalizedExpression>) -> FinalizedEffects {
+        return FinalizedEffects::CodeBody(FinalizedCodeBody::new(expressions, label.to_string(), false));
+    }
+
+    fn jump(label: &str) -> FinalizedEffects {
+        return FinalizedEffects::Jump(label.to_string());
+    }
+
+    fn compare(first: &str, second: &str) -> FinalizedEffects {
+        return FinalizedEffects::CompareJump(Box::new(FinalizedEffects::Bool(true)), first.to_string(), second.to_string());
+    }
+
+    #[test]
+    pub fn test_graph() {
+        // An if/else, then a while loop, then a return with a block after it.
+        let code = FinalizedCodeBody::new(vec!(
+            line(body("1", vec!(line(compare("2", "3")),
+                                line(body("2", vec!(line(jump("1end"))))),
+                                line(body("3", vec!(line(jump("1end")))))))),
+            line(body("4", vec!(line(compare("5", "4end")),
+                                line(body("5", vec!(line(jump("4")))))))),
+            FinalizedExpression::new(ExpressionType::Return, FinalizedEffects::Bool(true)),
+            line(body("6", vec!(FinalizedExpression::new(ExpressionType::Return, FinalizedEffects::Bool(false)))))),
+                                          "0".to_string(), true);
+        let graph = ControlFlowGraph::new(&code);
+
+        let edges: Vec<(&str, &str)> = graph.edges().into_iter().map(|(from, to)| (from.as_str(), to.as_str())).collect();
+        assert_eq!(edges, vec!(("0", "1"), ("1", "2"), ("1", "3"), ("2", "1end"), ("3", "1end"), ("1end", "4"),
+                               ("4", "5"), ("4", "4end"), ("5", "4")));
+        assert_eq!(graph.blocks.get("4end").unwrap().terminator, Terminator::Return);
+        assert_eq!(graph.unreachable(), vec!("6"));
+    }
+
+    #[test]
+    pub fn test_break() {
+        let code = FinalizedCodeBody::new(vec!(
+            FinalizedExpression::new(ExpressionType::Break, FinalizedEffects::Bool(true)),
+            line(FinalizedEffects::Bool(false))), "0".to_string(), false);
+        let graph = ControlFlowGraph::new(&code);
+
+        // A break isn't a return, and nothing after it is part of the block.
+        let block = graph.blocks.get("0").unwrap();
+        assert_eq!(block.terminator, Terminator::None);
+        assert_eq!(block.expressions.len(), 1);
+    }
+}
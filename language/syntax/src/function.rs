@@ -6,6 +6,7 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
 use async_trait::async_trait;
 use indexmap::IndexMap;
@@ -15,6 +16,10 @@ use crate::async_util::{AsyncDataGetter, HandleWrapper, NameResolver};
 use crate::code::{Expression, FinalizedEffects, FinalizedExpression, FinalizedMemberField, MemberField};
 use crate::types::FinalizedTypes;
 
+/// Separates the generics in a degenericed function name, like name$u64;str.
+/// Type names can contain underscores, so this has to be a character no type name can contain.
+pub const GENERIC_SEPARATOR: &str = ";";
+
 /// The static data of a function, which is set during parsing and immutable throughout the entire compilation process.
 /// Generics will copy this and change the name and types, but never modify the original.
 #[derive(Clone, Debug)]
@@ -92,11 +97,7 @@ impl TopElement for FunctionData {
         let mut locked = syntax.lock().unwrap();
 
         // Add the finalized code to the compiling list.
-        locked.compiling.write().unwrap().insert(name.clone(), finalized_function.clone());
-        for waker in &locked.compiling_wakers {
-            waker.wake_by_ref();
-        }
-        locked.compiling_wakers.clear();
+        locked.add_compiling(finalized_function.clone());
 
         if finalized_function.data.name == locked.async_manager.target {
             if let Some(found) = locked.async_manager.target_waker.as_ref() {
@@ -190,7 +191,7 @@ impl CodelessFinalizedFunction {
         // their solidified versions.
         // Degenericed function names have a $ seperating the name and the generics.
         let name = format!("{}${}", method.data.name.split("$").next().unwrap(), display_parenless(
-            &manager.generics().values().collect(), "_"));
+            &manager.generics().values().collect(), GENERIC_SEPARATOR));
         // If this function has already been degenericed, use the previous one.
         if syntax.lock().unwrap().functions.types.contains_key(&name) {
             let data = syntax.lock().unwrap().functions.types.get(&name).unwrap().clone();
@@ -252,10 +253,12 @@ impl Future for GenericWaiter {
     type Output = ();
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        return if self.syntax.lock().unwrap().compiling.read().unwrap().contains_key(&self.name) {
+        // Hold the lock between checking and registering so the function can't be added in between.
+        let mut locked = self.syntax.lock().unwrap();
+        return if locked.compiling.read().unwrap().contains_key(&self.name) {
             Poll::Ready(())
         } else {
-            self.syntax.lock().unwrap().compiling_wakers.push(cx.waker().clone());
+            locked.compiling_wakers.entry(self.name.clone()).or_insert_with(Vec::new).push(cx.waker().clone());
             Poll::Pending
         }
     }
@@ -266,6 +269,7 @@ async fn degeneric_code(syntax: Arc<Mutex<Syntax>>, original: Arc<CodelessFinali
                         degenericed_method: Arc<CodelessFinalizedFunction>, manager: Box<dyn ProcessManager>) {
     // This has to wait until the original is ready to be compiled.
     GenericWaiter { syntax: syntax.clone(), name: original.data.name.clone() }.await;
+    let start = Instant::now();
 
     // Gets a clone of the code of the original.
     let code = syntax.lock().unwrap().compiling.read().unwrap().get(&original.data.name).unwrap().code.clone();
@@ -283,11 +287,8 @@ async fn degeneric_code(syntax: Arc<Mutex<Syntax>>, original: Arc<CodelessFinali
 
     // Sends the finalized function to be compiled.
     let mut locked = syntax.lock().unwrap();
-    locked.compiling.write().unwrap().insert(output.data.name.clone(), Arc::new(output));
-    for waker in &locked.compiling_wakers {
-        waker.wake_by_ref();
-    }
-    locked.compiling_wakers.clear();
+    locked.record_timing("degeneric", start);
+    locked.add_compiling(Arc::new(output));
 }
 
 /// A finalized function, which is ready to be compiled and has been checked of any errors.
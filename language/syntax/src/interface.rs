@@ -0,0 +1,70 @@
+use indexmap::IndexMap;
+
+use crate::{is_modifier, Modifier};
+use crate::code::FinalizedMemberField;
+use crate::function::{CodelessFinalizedFunction, display_parenless};
+use crate::r#struct::FinalizedStruct;
+use crate::syntax::Syntax;
+use crate::types::FinalizedTypes;
+
+/// Emits the public interface of a module: every public structure, trait, and function signature, without any code.
+/// Each item is on its own line, sorted by name, so changes to the public API can be detected by diffing the output.
+/// Only works after the syntax is finished, because it reads the finalized types.
+pub fn emit_interface(syntax: &Syntax, module: &str) -> String {
+    let prefix = format!("{}::", module);
+    let mut output = Vec::new();
+
+    for (data, structure) in &syntax.structures.data {
+        if data.name.starts_with(&prefix) && is_modifier(data.modifiers, Modifier::Public) {
+            output.push((data.name.clone(), struct_signature(structure)));
+        }
+    }
+
+    for (data, function) in &syntax.functions.data {
+        if data.name.starts_with(&prefix) && is_modifier(data.modifiers, Modifier::Public) {
+            output.push((data.name.clone(), function_signature(function)));
+        }
+    }
+
+    output.sort_by(|first, second| first.0.cmp(&second.0));
+    return output.into_iter().map(|(_, signature)| signature + "\n").collect();
+}
+
+/// The signature of a structure or trait, including only public fields.
+fn struct_signature(structure: &FinalizedStruct) -> String {
+    let keyword = if is_modifier(structure.data.modifiers, Modifier::Trait) { "trait" } else { "struct" };
+    let fields: Vec<String> = structure.fields.iter()
+        .filter(|field| is_modifier(field.modifiers, Modifier::Public))
+        .map(|field| format!("pub {}: {}", field.field.name, field.field.field_type))
+        .collect();
+    return format!("pub {} {}{} {{ {} }}", keyword, structure.data.name,
+                   generics_signature(&structure.generics), fields.join(", "));
+}
+
+/// The signature of a function, without its code.
+fn function_signature(function: &CodelessFinalizedFunction) -> String {
+    let arguments: Vec<String> = function.arguments.iter().map(argument_signature).collect();
+    let returning = match &function.return_type {
+        Some(found) => format!(" -> {}", found),
+        None => String::new()
+    };
+    return format!("pub fn {}{}({}){};", function.data.name,
+                   generics_signature(&function.generics), arguments.join(", "), returning);
+}
+
+fn argument_signature(argument: &FinalizedMemberField) -> String {
+    return format!("{}: {}", argument.field.name, argument.field.field_type);
+}
+
+fn generics_signature(generics: &IndexMap<String, Vec<FinalizedTypes>>) -> String {
+    if generics.is_empty() {
+        return String::new();
+    }
+
+    let generics: Vec<String> = generics.iter().map(|(name, bounds)| if bounds.is_empty() {
+        name.clone()
+    } else {
+        format!("{}: {}", name, display_parenless(bounds, " + "))
+    }).collect();
+    return format!("<{}>", generics.join(", "));
+}
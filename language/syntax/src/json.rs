@@ -0,0 +1,194 @@
+use crate::Attribute;
+use crate::code::{Effects, Expression, ExpressionType};
+use crate::function::{CodeBody, UnfinalizedFunction};
+use crate::r#struct::UnfinalizedStruct;
+use crate::to_modifiers;
+
+/// The version of the JSON format, increased whenever the format changes in a way that breaks readers.
+/// Version 1 has no spans, because effects don't keep their position in the file. Adding them will be version 2.
+pub const AST_JSON_VERSION: u32 = 1;
+
+/// Serializes a parsed code body to JSON so tools not written in Rust can read it.
+/// The format is:
+/// - A document is {"version": AST_JSON_VERSION, "body": body}
+/// - A body is {"label": string, "expressions": [expression]}
+/// - An expression is {"type": "Line" | "Return" | "Break", "effect": effect}
+/// - An effect is {"kind": the Effects variant name, ...fields}, see effect_to_json for the fields of each kind.
+///   Floats that JSON can't represent are the strings "NaN", "Infinity", and "-Infinity".
+pub fn code_to_json(code: &CodeBody) -> String {
+    return format!("{{\"version\":{},\"body\":{}}}", AST_JSON_VERSION, body_to_json(code));
+}
+
+/// Serializes a parsed function to JSON, in the same format as code_to_json.
+/// - A document is {"version": AST_JSON_VERSION, "function": function}
+/// - A function is {"name": string, "modifiers": [string], "attributes": [attribute], "code": body}
+/// - An attribute is {"name": string, "value": null | number | bool | string}
+/// Argument, generic, and return types are still being parsed at this point, so they aren't included.
+pub fn function_to_json(function: &UnfinalizedFunction) -> String {
+    return format!("{{\"version\":{},\"function\":{}}}", AST_JSON_VERSION, function_json(function));
+}
+
+/// Serializes a parsed structure to JSON, in the same format as function_to_json.
+/// - A document is {"version": AST_JSON_VERSION, "struct": struct}
+/// - A struct is {"name": string, "modifiers": [string], "attributes": [attribute], "functions": [function]}
+/// Fields and generics are still being parsed at this point, so they aren't included.
+pub fn struct_to_json(structure: &UnfinalizedStruct) -> String {
+    let functions: Vec<String> = structure.functions.iter().map(function_json).collect();
+    return format!("{{\"version\":{},\"struct\":{{\"name\":{},\"modifiers\":{},\"attributes\":{},\"functions\":[{}]}}}}",
+                   AST_JSON_VERSION, string(&structure.data.name), modifiers_to_json(structure.data.modifiers),
+                   attributes_to_json(&structure.data.attributes), functions.join(","));
+}
+
+fn function_json(function: &UnfinalizedFunction) -> String {
+    return format!("{{\"name\":{},\"modifiers\":{},\"attributes\":{},\"code\":{}}}",
+                   string(&function.data.name), modifiers_to_json(function.data.modifiers),
+                   attributes_to_json(&function.data.attributes), body_to_json(&function.code));
+}
+
+fn modifiers_to_json(modifiers: u8) -> String {
+    let modifiers: Vec<String> = to_modifiers(modifiers).iter().map(|modifier| string(&modifier.to_string())).collect();
+    return format!("[{}]", modifiers.join(","));
+}
+
+fn attributes_to_json(attributes: &Vec<Attribute>) -> String {
+    let attributes: Vec<String> = attributes.iter().map(|attribute| {
+        let (name, value) = match attribute {
+            Attribute::Basic(name) => (name, "null".to_string()),
+            Attribute::Integer(name, value) => (name, value.to_string()),
+            Attribute::Bool(name, value) => (name, value.to_string()),
+            Attribute::String(name, value) => (name, string(value))
+        };
+        format!("{{\"name\":{},\"value\":{}}}", string(name), value)
+    }).collect();
+    return format!("[{}]", attributes.join(","));
+}
+
+fn body_to_json(code: &CodeBody) -> String {
+    let expressions: Vec<String> = code.expressions.iter().map(expression_to_json).collect();
+    return format!("{{\"label\":{},\"expressions\":[{}]}}", string(&code.label), expressions.join(","));
+}
+
+fn expression_to_json(expression: &Expression) -> String {
+    let expression_type = match expression.expression_type {
+        ExpressionType::Line => "Line",
+        ExpressionType::Return => "Return",
+        ExpressionType::Break => "Break"
+    };
+    return format!("{{\"type\":\"{}\",\"effect\":{}}}", expression_type, effect_to_json(&expression.effect));
+}
+
+fn effect_to_json(effect: &Effects) -> String {
+    let (kind, fields) = match effect {
+        Effects::NOP() => ("NOP", String::new()),
+        Effects::Paren(inner) => ("Paren", format!("\"effect\":{}", effect_to_json(inner))),
+        Effects::CreateVariable(name, value) =>
+            ("CreateVariable", format!("\"name\":{},\"value\":{}", string(name), effect_to_json(value))),
+        Effects::Jump(label) => ("Jump", format!("\"label\":{}", string(label))),
+        Effects::CompareJump(condition, then, otherwise) =>
+            ("CompareJump", format!("\"condition\":{},\"then\":{},\"else\":{}", effect_to_json(condition), string(then), string(otherwise))),
+        Effects::CodeBody(body) => ("CodeBody", format!("\"body\":{}", body_to_json(body))),
+        Effects::ImplementationCall(calling, traits, method, arguments, returning) =>
+            ("ImplementationCall", format!("\"calling\":{},\"trait\":{},\"method\":{},\"arguments\":{},\"returning\":{}",
+                    effect_to_json(calling), string(traits), string(method), effects_to_json(arguments),
+                    optional_string(returning.as_ref().map(|inner| inner.to_string())))),
+        Effects::MethodCall(calling, method, arguments, returning) =>
+            ("MethodCall", format!("\"calling\":{},\"method\":{},\"arguments\":{},\"returning\":{}",
+                    calling.as_ref().map_or("null".to_string(), |inner| effect_to_json(inner)), string(method),
+                    effects_to_json(arguments), optional_string(returning.as_ref().map(|inner| inner.to_string())))),
+        Effects::Set(target, value) =>
+            ("Set", format!("\"target\":{},\"value\":{}", effect_to_json(target), effect_to_json(value))),
+        Effects::LoadVariable(name) => ("LoadVariable", format!("\"name\":{}", string(name))),
+        Effects::Load(from, field) => ("Load", format!("\"from\":{},\"field\":{}", effect_to_json(from), string(field))),
+        Effects::Operation(operation, arguments) =>
+            ("Operation", format!("\"operation\":{},\"arguments\":{}", string(operation), effects_to_json(arguments))),
+        Effects::CreateStruct(types, fields) => {
+            let fields: Vec<String> = fields.iter()
+                .map(|(name, value)| format!("{{\"name\":{},\"value\":{}}}", string(name), effect_to_json(value)))
+                .collect();
+            ("CreateStruct", format!("\"type\":{},\"fields\":[{}]", string(&types.to_string()), fields.join(",")))
+        }
+        Effects::CreateArray(values) => ("CreateArray", format!("\"values\":{}", effects_to_json(values))),
+        Effects::Float(value) => ("Float", format!("\"value\":{}", float(*value))),
+        Effects::Int(value) => ("Int", format!("\"value\":{}", value)),
+        Effects::UInt(value) => ("UInt", format!("\"value\":{}", value)),
+        Effects::Bool(value) => ("Bool", format!("\"value\":{}", value)),
+        Effects::Char(value) => ("Char", format!("\"value\":{}", string(&value.to_string()))),
+        Effects::String(value) => ("String", format!("\"value\":{}", string(value)))
+    };
+
+    return if fields.is_empty() {
+        format!("{{\"kind\":\"{}\"}}", kind)
+    } else {
+        format!("{{\"kind\":\"{}\",{}}}", kind, fields)
+    };
+}
+
+fn effects_to_json(effects: &Vec<Effects>) -> String {
+    let effects: Vec<String> = effects.iter().map(effect_to_json).collect();
+    return format!("[{}]", effects.join(","));
+}
+
+fn optional_string(input: Option<String>) -> String {
+    return input.map_or("null".to_string(), |inner| string(&inner));
+}
+
+/// Formats a float for JSON, which has no numbers for NaN or the infinities.
+fn float(input: f64) -> String {
+    return if input.is_nan() {
+        string("NaN")
+    } else if input.is_infinite() {
+        string(if input > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        input.to_string()
+    };
+}
+
+/// Quotes and escapes a string for JSON.
+fn string(input: &str) -> String {
+    let mut output = String::from("\"");
+    for character in input.chars() {
+        match character {
+            '"' => output += "\\\"",
+            '\\' => output += "\\\\",
+            '\n' => output += "\\n",
+            '\r' => output += "\\r",
+            '\t' => output += "\\t",
+            character if (character as u32) < 0x20 => output += &format!("\\u{:04x}", character as u32),
+            character => output.push(character)
+        }
+    }
+    output.push('"');
+    return output;
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use indexmap::IndexMap;
+    use crate::{Attribute, Modifier};
+    use crate::code::{Effects, Expression, ExpressionType};
+    use crate::function::{CodeBody, FunctionData, UnfinalizedFunction};
+    use crate::json::function_to_json;
+
+    #[test]
+    pub fn test_function_json() {
+        let function = UnfinalizedFunction {
+            generics: IndexMap::new(),
+            fields: vec!(),
+            code: CodeBody::new(vec!(
+                Expression::new(ExpressionType::Line,
+                                Effects::CreateVariable("value".to_string(), Box::new(Effects::Float(f64::NAN)))),
+                Expression::new(ExpressionType::Return, Effects::Operation("{}+{}".to_string(),
+                    vec!(Effects::LoadVariable("value".to_string()), Effects::Int(1))))), "0".to_string()),
+            return_type: None,
+            data: Arc::new(FunctionData::new(vec!(Attribute::Integer("init".to_string(), 1)),
+                                             Modifier::Public as u8, "test::function".to_string())),
+        };
+
+        assert_eq!(function_to_json(&function), "{\"version\":1,\"function\":{\"name\":\"test::function\",\
+            \"modifiers\":[\"pub\"],\"attributes\":[{\"name\":\"init\",\"value\":1}],\"code\":{\"label\":\"0\",\"expressions\":[\
+            {\"type\":\"Line\",\"effect\":{\"kind\":\"CreateVariable\",\"name\":\"value\",\"value\":{\"kind\":\"Float\",\"value\":\"NaN\"}}},\
+            {\"type\":\"Return\",\"effect\":{\"kind\":\"Operation\",\"operation\":\"{}+{}\",\"arguments\":[\
+            {\"kind\":\"LoadVariable\",\"name\":\"value\"},{\"kind\":\"Int\",\"value\":1}]}}]}}}");
+    }
+}
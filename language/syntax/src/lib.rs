@@ -33,8 +33,12 @@ pub mod async_util;
 pub mod chalk_interner;
 pub mod chalk_support;
 pub mod code;
+pub mod control_flow;
 pub mod function;
+pub mod interface;
+pub mod json;
 pub mod operation_util;
+pub mod pretty;
 pub mod r#struct;
 pub mod syntax;
 pub mod types;
@@ -150,6 +154,19 @@ impl Attribute {
     }
 }
 
+/// A step of compilation, sent to the process manager so embedders can follow along without polling the syntax.
+#[derive(Clone, Debug)]
+pub enum CompileEvent {
+    // Every file has been parsed, so anything not found yet doesn't exist
+    ParseDone,
+    // A function finished being checked and was sent to be compiled, by its name
+    FunctionFinalized(String),
+    // A structure finished being checked, by its name
+    StructFinalized(String),
+    // An error was added to the syntax
+    Errored(ParsingError),
+}
+
 #[async_trait]
 pub trait ProcessManager: Send + Sync {
     fn handle(&self) -> &Arc<Mutex<HandleWrapper>>;
@@ -165,6 +182,9 @@ pub trait ProcessManager: Send + Sync {
     fn mut_generics(&mut self) -> &mut HashMap<String, FinalizedTypes>;
 
     fn cloned(&self) -> Box<dyn ProcessManager>;
+
+    /// Called as compilation moves forward, for things like progress bars. Does nothing by default.
+    fn on_phase(&self, _event: CompileEvent) {}
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,114 @@
+use std::fmt::{Display, Formatter};
+
+use indexmap::IndexMap;
+
+use crate::code::{ExpressionType, FinalizedEffects, FinalizedMemberField};
+use crate::function::{display_parenless, FinalizedCodeBody, FinalizedFunction, GENERIC_SEPARATOR};
+use crate::types::FinalizedTypes;
+
+/// Prints a finalized function back as source-like text, for inspecting the compiler's IR while debugging.
+/// Code bodies are printed as labelled blocks, with nested bodies indented one level further.
+impl Display for FinalizedFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let arguments: Vec<String> = self.fields.iter().map(argument).collect();
+        let returning = match &self.return_type {
+            Some(found) => format!(" -> {}", found),
+            None => String::new()
+        };
+        write!(f, "fn {}{}({}){} ", readable_name(&self.data.name), generics(&self.generics),
+               arguments.join(", "), returning)?;
+        return write!(f, "{}", body(&self.code, 0));
+    }
+}
+
+/// Degenericed names have a $ separating the name and the generics, so print those as name<generics> instead.
+fn readable_name(name: &str) -> String {
+    return match name.split_once('$') {
+        Some((name, generics)) => {
+            let generics: Vec<&str> = generics.split(GENERIC_SEPARATOR).collect();
+            format!("{}<{}>", name, generics.join(", "))
+        }
+        None => name.to_string()
+    };
+}
+
+fn argument(argument: &FinalizedMemberField) -> String {
+    return format!("{}: {}", argument.field.name, argument.field.field_type);
+}
+
+fn generics(generics: &IndexMap<String, Vec<FinalizedTypes>>) -> String {
+    if generics.is_empty() {
+        return String::new();
+    }
+
+    let generics: Vec<String> = generics.iter().map(|(name, bounds)| if bounds.is_empty() {
+        name.clone()
+    } else {
+        format!("{}: {}", name, display_parenless(bounds, " + "))
+    }).collect();
+    return format!("<{}>", generics.join(", "));
+}
+
+fn body(code: &FinalizedCodeBody, depth: usize) -> String {
+    let indent = "    ".repeat(depth + 1);
+    let mut output = format!("{} {{\n", code.label);
+    for expression in &code.expressions {
+        let effect = effect(&expression.effect, depth + 1);
+        output += &match expression.expression_type {
+            ExpressionType::Line => format!("{}{};\n", indent, effect),
+            ExpressionType::Return => format!("{}return {};\n", indent, effect),
+            ExpressionType::Break => format!("{}break {};\n", indent, effect)
+        };
+    }
+    output += &format!("{}}}", "    ".repeat(depth));
+    return output;
+}
+
+fn effect(effect: &FinalizedEffects, depth: usize) -> String {
+    return match effect {
+        FinalizedEffects::NOP() => String::new(),
+        FinalizedEffects::CreateVariable(name, value, types) =>
+            format!("let {}: {} = {}", name, types, self::effect(value, depth)),
+        FinalizedEffects::Jump(label) => format!("jump {}", label),
+        FinalizedEffects::CompareJump(condition, then, otherwise) =>
+            format!("if {} jump {} else {}", self::effect(condition, depth), then, otherwise),
+        FinalizedEffects::CodeBody(code) => body(code, depth),
+        FinalizedEffects::MethodCall(calling, function, arguments) => match calling {
+            Some(calling) => format!("{}.{}({})", self::effect(calling, depth),
+                                     readable_name(&function.data.name), effects(arguments, depth)),
+            None => format!("{}({})", readable_name(&function.data.name), effects(arguments, depth))
+        },
+        FinalizedEffects::GenericMethodCall(function, types, arguments) =>
+            format!("<{}>::{}({})", types, readable_name(&function.data.name), effects(arguments, depth)),
+        FinalizedEffects::Set(target, value) =>
+            format!("{} = {}", self::effect(target, depth), self::effect(value, depth)),
+        FinalizedEffects::LoadVariable(name) => name.clone(),
+        FinalizedEffects::Load(from, field, _) => format!("{}.{}", self::effect(from, depth), field),
+        FinalizedEffects::CreateStruct(_, types, fields) => {
+            let fields: Vec<String> = fields.iter()
+                .map(|(index, value)| format!("{}: {}", index, self::effect(value, depth)))
+                .collect();
+            format!("new {} {{ {} }}", types, fields.join(", "))
+        }
+        FinalizedEffects::CreateArray(_, values) => format!("[{}]", effects(values, depth)),
+        FinalizedEffects::Float(value) => value.to_string(),
+        FinalizedEffects::UInt(value) => value.to_string(),
+        FinalizedEffects::Bool(value) => value.to_string(),
+        FinalizedEffects::String(value) => format!("{:?}", value),
+        FinalizedEffects::Char(value) => format!("{:?}", value),
+        FinalizedEffects::VirtualCall(index, function, arguments) =>
+            format!("virtual[{}] {}({})", index, readable_name(&function.data.name), effects(arguments, depth)),
+        FinalizedEffects::GenericVirtualCall(index, _, function, arguments) =>
+            format!("virtual[{}] {}({})", index, readable_name(&function.data.name), effects(arguments, depth)),
+        FinalizedEffects::Downcast(inner, types) => format!("({} as {})", self::effect(inner, depth), types),
+        FinalizedEffects::HeapStore(inner) => format!("heap({})", self::effect(inner, depth)),
+        FinalizedEffects::HeapAllocate(types) => format!("heap_alloc({})", types),
+        FinalizedEffects::ReferenceLoad(inner) => format!("*{}", self::effect(inner, depth)),
+        FinalizedEffects::StackStore(inner) => format!("stack({})", self::effect(inner, depth))
+    };
+}
+
+fn effects(effects: &Vec<FinalizedEffects>, depth: usize) -> String {
+    let effects: Vec<String> = effects.iter().map(|inner| effect(inner, depth)).collect();
+    return effects.join(", ");
+}
@@ -7,7 +7,7 @@ use std::sync::Mutex;
 use indexmap::map::IndexMap;
 use lazy_static::lazy_static;
 use async_trait::async_trait;
-use crate::{DataType, is_modifier, Modifier, ParsingFuture, ProcessManager, Syntax, TopElement};
+use crate::{CompileEvent, DataType, is_modifier, Modifier, ParsingFuture, ProcessManager, Syntax, TopElement};
 use crate::code::{FinalizedMemberField, MemberField};
 use crate::{Attribute, ParsingError};
 use crate::top_element_manager::TopElementManager;
@@ -209,6 +209,20 @@ impl StructData {
         }
     }
 
+    /// The priority of an operator from its priority attribute, defaulting to 0.
+    /// Operators with higher priorities bind tighter, so 1 + 2 * 3 is 1 + (2 * 3).
+    pub fn priority(&self) -> i64 {
+        return Attribute::find_attribute("priority", &self.attributes)
+            .map(|inner| inner.as_int_attribute().unwrap_or(0)).unwrap_or(0);
+    }
+
+    /// If an operator groups to the right when next to an operator of the same priority, from its parse_left attribute.
+    /// Operators group to the left by default, so 10 - 3 - 2 is (10 - 3) - 2.
+    pub fn parse_left(&self) -> bool {
+        return Attribute::find_attribute("parse_left", &self.attributes)
+            .map(|inner| inner.as_bool_attribute().unwrap_or(false)).unwrap_or(false);
+    }
+
     pub fn new_poisoned(name: String, error: ParsingError) -> Self {
         let mut output = Self::new(Vec::new(), Vec::new(), 0, name);
         output.poisoned = vec!(error);
@@ -225,7 +239,24 @@ impl FinalizedStruct {
         };
     }
 
+    /// Gets the name and type of every field in declaration order, for generating code like serializers.
+    /// Fields without the public modifier are skipped unless include_private is set.
+    pub fn reflect_fields(&self, include_private: bool) -> Vec<(&String, &FinalizedTypes)> {
+        return self.fields.iter()
+            .filter(|field| include_private || is_modifier(field.modifiers, Modifier::Public))
+            .map(|field| (&field.field.name, &field.field.field_type))
+            .collect();
+    }
+
+    /// Replaces the structure's generics with the given types, in the order they were declared.
+    /// Fails if the wrong number of types is given or one of them doesn't satisfy its generic's bounds.
     pub async fn degeneric(&mut self, generics: &Vec<FinalizedTypes>, syntax: &Arc<Mutex<Syntax>>) -> Result<(), ParsingError> {
+        let expected: usize = self.generics.values().map(|value| value.len()).sum();
+        if generics.len() != expected {
+            return Err(placeholder_error(format!("{} expects {} generics, but was given {}!",
+                                                 self.data.name, expected, generics.len())));
+        }
+
         let mut i = 0;
         for value in self.generics.values_mut() {
             for generic in value {
@@ -234,13 +265,14 @@ impl FinalizedStruct {
                     let temp: &FinalizedTypes = generics.get(i).unwrap();
                     for bound in bounds {
                         if !temp.of_type(&bound, syntax.clone()).await {
-                            panic!("Generic {} set to a {} which isn't a {}", name, temp.name(), bound.name());
+                            return Err(placeholder_error(format!("Generic {} set to a {} which isn't a {}!",
+                                                                 name, temp.name(), bound.name())));
                         }
                     }
                     *generic = temp.clone();
                     i += 1;
                 } else {
-                    panic!("Guhh?????");
+                    return Err(placeholder_error(format!("Expected {} to be a generic in {}!", generic, self.data.name)));
                 }
             }
         }
@@ -248,7 +280,10 @@ impl FinalizedStruct {
         for field in &mut self.fields {
             let types = &mut field.field.field_type;
             if let FinalizedTypes::Generic(name, _) = types {
-                let index = self.generics.iter().position(|(other_name, _)| name == other_name).unwrap();
+                let index = match self.generics.iter().position(|(other_name, _)| name == other_name) {
+                    Some(index) => index,
+                    None => return Err(placeholder_error(format!("Unknown generic {} in {}!", name, self.data.name)))
+                };
                 let generic: &FinalizedTypes = generics.get(index).unwrap();
                 *types = generic.clone();
             }
@@ -306,6 +341,7 @@ impl TopElement for StructData {
             }
 
             locked.structures.data.insert(data.clone(), structure.clone());
+            locked.process_manager.on_phase(CompileEvent::StructFinalized(data.name.clone()));
         }
 
         for function in functions {
@@ -317,12 +353,7 @@ impl TopElement for StructData {
 
             let function = process_manager.verify_code(function, code, resolver.boxed_clone(), &syntax).await;
 
-            let mut locked = syntax.lock().unwrap();
-            locked.compiling.write().unwrap().insert(function.data.name.clone(), Arc::new(function));
-            for waker in &locked.compiling_wakers {
-                waker.wake_by_ref();
-            }
-            locked.compiling_wakers.clear();
+            syntax.lock().unwrap().add_compiling(Arc::new(function));
         }
         handle.lock().unwrap().finish_task(&data.name);
     }
@@ -340,4 +371,8 @@ impl Debug for StructData {
 
 impl Eq for StructData {}
 
-impl Eq for FinalizedStruct {}
\ No newline at end of file
+impl Eq for FinalizedStruct {}
+
+fn placeholder_error(error: String) -> ParsingError {
+    return ParsingError::new(String::new(), (0, 0), 0, (0, 0), 0, error);
+}
@@ -1,13 +1,16 @@
 use std::collections::HashMap;
 use std::ops::DerefMut;
 use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::task::Waker;
 use std::mem;
-use chalk_ir::{Binders, DomainGoal, GenericArg, GenericArgData, Goal, GoalData, Substitution, TraitId, TraitRef, TyVariableKind, VariableKind, VariableKinds, WhereClause};
+use std::time::Instant;
+use chalk_ir::{Binders, BoundVar, DebruijnIndex, DomainGoal, GenericArg, GenericArgData, Goal, GoalData, Substitution, TraitId, TraitRef, TyKind, TyVariableKind, VariableKind, VariableKinds, WhereClause};
 use chalk_recursive::RecursiveSolver;
 use chalk_solve::rust_ir::{ImplDatum, ImplDatumBound, ImplType, Polarity};
 use chalk_solve::Solver;
 use chalk_solve::ext::GoalExt;
+use data::Timings;
 use indexmap::IndexMap;
 use std::sync::Mutex;
 use tokio::sync::mpsc::Receiver;
@@ -18,7 +21,7 @@ use async_trait::async_trait;
 // Re-export main
 pub use data::Main;
 
-use crate::{Attribute, FinishedTraitImplementor, is_modifier, Modifier, ParsingError, ProcessManager, TopElement, Types};
+use crate::{Attribute, CompileEvent, FinishedTraitImplementor, is_modifier, Modifier, ParsingError, ProcessManager, TopElement, Types};
 use crate::top_element_manager::{TopElementManager, GetterManager};
 use crate::async_util::{AsyncTypesGetter, NameResolver, UnparsedType};
 use crate::chalk_interner::ChalkIr;
@@ -33,12 +36,16 @@ use crate::types::FinalizedTypes;
 pub struct Syntax {
     // The compiling functions, accessed from the compiler.
     pub compiling: Arc<RwLock<HashMap<String, Arc<FinalizedFunction>>>>,
-    // Compiling wakers
-    pub compiling_wakers: Vec<Waker>,
+    // Wakers waiting for a function to be added to compiling, by function name
+    pub compiling_wakers: HashMap<String, Vec<Waker>>,
     // The compiling structs, accessed from the compiler..
     pub strut_compiling: Arc<RwLock<HashMap<String, Arc<FinalizedStruct>>>>,
+    // The field types of each structure by name, added before the fields are finalized to find recursive structures
+    pub struct_fields: HashMap<String, Vec<Types>>,
     // All parsing errors on the entire program
     pub errors: Vec<ParsingError>,
+    // All warnings on the entire program, which are printed but don't stop compilation
+    pub warnings: Vec<ParsingError>,
     // All structures in the program
     pub structures: TopElementManager<StructData>,
     // All functions in the program
@@ -54,6 +61,16 @@ pub struct Syntax {
     pub operation_wakers: HashMap<String, Vec<Waker>>,
     // Manages the next steps of compilation after parsing
     pub process_manager: Box<dyn ProcessManager>,
+    // The amount of times the Chalk solver has been called, for profiling.
+    pub solver_calls: AtomicU64,
+    // How deep the Chalk solver can recurse before it gives up on a goal
+    pub solver_overflow_depth: usize,
+    // The largest a type can grow to in the Chalk solver before it gives up on a goal
+    pub solver_max_size: usize,
+    // Chalk solver results by the ids of the type and trait, only filled once implementations are finished.
+    pub solve_cache: RwLock<HashMap<(u64, u64), bool>>,
+    // If set, the time each phase takes is recorded here
+    pub timings: Option<Arc<Mutex<Timings>>>,
 }
 
 impl Syntax {
@@ -61,9 +78,11 @@ impl Syntax {
     pub fn new(process_manager: Box<dyn ProcessManager>) -> Self {
         return Self {
             compiling: Arc::new(RwLock::new(HashMap::new())),
-            compiling_wakers: Vec::new(),
+            compiling_wakers: HashMap::new(),
             strut_compiling: Arc::new(RwLock::new(HashMap::new())),
+            struct_fields: HashMap::new(),
             errors: Vec::new(),
+            warnings: Vec::new(),
             functions: TopElementManager::new(),
             structures: TopElementManager::with_sorted(
                 vec!(I64.data.clone(), I32.data.clone(), I16.data.clone(), I8.data.clone(),
@@ -74,20 +93,83 @@ impl Syntax {
             operations: HashMap::new(),
             operation_wakers: HashMap::new(),
             process_manager,
+            solver_calls: AtomicU64::new(0),
+            solver_overflow_depth: 30,
+            solver_max_size: 3000,
+            solve_cache: RwLock::new(HashMap::new()),
+            timings: None,
         };
     }
 
+    /// Sets the limits of the Chalk solver. Programs with deep trait hierarchies may need higher limits,
+    /// or else implementations past the limit won't be found.
+    pub fn set_solver_limits(&mut self, overflow_depth: usize, max_size: usize) {
+        self.solver_overflow_depth = overflow_depth;
+        self.solver_max_size = max_size;
+    }
+
     /// Checks if the implementations are finished parsing.
     pub fn finished_impls(&self) -> bool {
         return self.async_manager.finished && self.async_manager.parsing_impls == 0;
     }
 
+    /// Adds a finalized function to be compiled, waking anything waiting for that function.
+    pub fn add_compiling(&mut self, function: Arc<FinalizedFunction>) {
+        let name = function.data.name.clone();
+        self.process_manager.on_phase(CompileEvent::FunctionFinalized(name.clone()));
+        self.compiling.write().unwrap().insert(name.clone(), function);
+        if let Some(wakers) = self.compiling_wakers.remove(&name) {
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    }
+
+    /// The functions each compiling function calls, by their degenericed names so every generic version is separate.
+    /// Operators are included, because they're lowered to method calls before this point.
+    /// Useful for finding why a function is or isn't compiled, or which functions can't be reached from the main.
+    pub fn call_graph(&self) -> HashMap<String, Vec<String>> {
+        let mut output = HashMap::new();
+        for (name, function) in self.compiling.read().unwrap().iter() {
+            let mut calls = Vec::new();
+            for expression in &function.code.expressions {
+                expression.effect.calls(&mut calls);
+            }
+            calls.sort();
+            calls.dedup();
+            output.insert(name.clone(), calls);
+        }
+        return output;
+    }
+
+    /// Adds the time since the start to the phase's timings, if timings are being recorded.
+    pub fn record_timing(&self, phase: &str, start: Instant) {
+        Timings::record(&self.timings, phase, start);
+    }
+
+    /// Adds an error to the syntax, telling the process manager about it.
+    pub fn add_error(&mut self, error: ParsingError) {
+        self.process_manager.on_phase(CompileEvent::Errored(error.clone()));
+        self.errors.push(error);
+    }
+
+    /// Every operation that has been parsed with its priority and if it groups to the right (see StructData::parse_left),
+    /// sorted from the tightest binding to the loosest. Useful for tools like formatters that need to add parenthesis.
+    pub fn operator_table(&self) -> Vec<(String, i64, bool)> {
+        let mut output: Vec<(String, i64, bool)> = self.operations.iter()
+            .map(|(operation, data)| (operation.clone(), data.priority(), data.parse_left()))
+            .collect();
+        output.sort_by(|first, second| second.1.cmp(&first.1).then_with(|| first.0.cmp(&second.0)));
+        return output;
+    }
+
     /// Sets the syntax to be finished, calling all wakers so non-existent functions can be detected.
     pub fn finish(&mut self) {
         if self.async_manager.finished {
             panic!("Tried to finish already-finished syntax!")
         }
         self.async_manager.finished = true;
+        self.process_manager.on_phase(CompileEvent::ParseDone);
 
         let mut keys = Vec::new();
         self.structures.wakers.keys().for_each(|inner| keys.push(inner.clone()));
@@ -121,9 +203,26 @@ impl Syntax {
         let vec_generics = generics.keys().collect::<Vec<_>>();
         let first = first.to_chalk_trait(&vec_generics);
         let mut binders: Vec<VariableKind<ChalkIr>> = Vec::new();
+        let mut where_clauses = Vec::new();
         // We resolve generics ourselves, but Chalk needs to know about them.
-        for _value in generics.values() {
+        for (index, bounds) in generics.values().enumerate() {
             binders.push(VariableKind::Ty(TyVariableKind::General));
+
+            // Each where clause has its own binder, so the impl's generics are one level out.
+            let generic = TyKind::BoundVar(BoundVar { debruijn: DebruijnIndex::ONE, index }).intern(ChalkIr);
+            for bound in bounds {
+                // Only trait bounds can be checked by Chalk.
+                if !matches!(bound, FinalizedTypes::Struct(_, _) | FinalizedTypes::GenericType(_, _)) ||
+                    !is_modifier(bound.inner_struct().data.modifiers, Modifier::Trait) {
+                    continue;
+                }
+
+                let data: &[GenericArg<ChalkIr>] = &[GenericArg::new(ChalkIr, GenericArgData::Ty(generic.clone()))];
+                where_clauses.push(Binders::empty(ChalkIr, WhereClause::Implemented(TraitRef {
+                    trait_id: bound.to_chalk_trait(&vec_generics).id,
+                    substitution: Substitution::from_iter(ChalkIr, data),
+                })));
+            }
         }
         let second = second.to_chalk_type(&vec_generics);
         let data: &[GenericArg<ChalkIr>] = &[GenericArg::new(ChalkIr, GenericArgData::Ty(second.clone()))];
@@ -131,7 +230,7 @@ impl Syntax {
             polarity: Polarity::Positive,
             binders: Binders::new(VariableKinds::from_iter(ChalkIr, binders), ImplDatumBound {
                 trait_ref: TraitRef { trait_id: first.id.clone(), substitution: Substitution::from_iter(ChalkIr, data) },
-                where_clauses: vec![],
+                where_clauses,
             }),
             impl_type: ImplType::Local,
             associated_ty_value_ids: vec![],
@@ -210,6 +309,19 @@ impl Syntax {
         if !is_modifier(second_ty.modifiers, Modifier::Trait) {
             return false;
         }
+
+        // Most implementations are a concrete type implementing a trait directly, which doesn't need Chalk.
+        if self.solve_direct(first, second_ty) {
+            return true;
+        }
+
+        // The goal only depends on the type and trait, so the result can be reused for the same pair.
+        let key = (first.inner_struct().data.id, second_ty.id);
+        if let Some(found) = self.solve_cache.read().unwrap().get(&key) {
+            return *found;
+        }
+
+        self.solver_calls.fetch_add(1, Ordering::Relaxed);
         let first_ty = first.inner_struct().data.chalk_data.as_ref().unwrap().get_ty().clone();
 
         let elements: &[GenericArg<ChalkIr>] = &[GenericArg::new(ChalkIr, GenericArgData::Ty(first_ty))];
@@ -222,12 +334,26 @@ impl Syntax {
         )));
 
         // Tell Chalk to solve it, ignoring any overflows.
-        // TODO add a cache for speed?
-        let value = RecursiveSolver::new(30, 3000, None)
+        let value = RecursiveSolver::new(self.solver_overflow_depth, self.solver_max_size, None)
             .solve(self, &goal.into_closed_goal(ChalkIr)).is_some();
+        // New implementations can change the result until they're all parsed.
+        if self.finished_impls() {
+            self.solve_cache.write().unwrap().insert(key, value);
+        }
         return value;
     }
 
+    /// Checks if there is a non-generic implementation of the trait for the type, which can be checked without Chalk.
+    fn solve_direct(&self, first: &FinalizedTypes, second: &Arc<StructData>) -> bool {
+        let first = &first.inner_struct().data;
+        return self.implementations.iter().any(|implementation| implementation.generics.is_empty() &&
+            &implementation.target.inner_struct().data == second &&
+            match &implementation.base {
+                FinalizedTypes::Struct(base, _) => &base.data == first,
+                _ => false
+            });
+    }
+
     /// Adds the element to the syntax
     pub fn add<T: TopElement + Eq + 'static>(syntax: &Arc<Mutex<Syntax>>, dupe_error: ParsingError, adding: &Arc<T>) {
         let mut locked = syntax.lock().unwrap();
@@ -240,14 +366,14 @@ impl Syntax {
 
         // Add any poisons to the syntax errors list.
         for poison in adding.errors() {
-            locked.errors.push(poison.clone());
+            locked.add_error(poison.clone());
         }
 
         // Checks if a type with the same name is already in the async manager.
         if let Some(mut old) = T::get_manager(locked.deref_mut()).types.get_mut(adding.name()).cloned() {
             if adding.errors().is_empty() && adding.errors().is_empty() {
                 // Add a duplication error to the original type.
-                locked.errors.push(dupe_error.clone());
+                locked.add_error(dupe_error.clone());
                 unsafe { Arc::get_mut_unchecked(&mut old) }.poison(dupe_error.clone());
             } else {
                 // Ignored if one is poisoned
@@ -275,14 +401,14 @@ impl Syntax {
                 _ => {
                     let mut error = ParsingError::empty();
                     error.message = format!("Expected a string with attribute operator!");
-                    locked.errors.push(error);
+                    locked.add_error(error);
                     return;
                 }
             };
 
             // Checks if there is a duplicate of that operation.
             if locked.operations.contains_key(&name) {
-                locked.errors.push(dupe_error);
+                locked.add_error(dupe_error);
             }
 
             locked.operations.insert(name.clone(), adding);
@@ -306,7 +432,7 @@ impl Syntax {
     /// Adds a poisoned type, which means it errored and shouldn't be checked for completeness.
     pub fn add_poison<T: TopElement>(&mut self, element: Arc<T>) {
         for poison in element.errors() {
-            self.errors.push(poison.clone());
+            self.add_error(poison.clone());
         }
 
         let getter = T::get_manager(self);
@@ -430,4 +556,173 @@ pub trait Compiler<T> {
     /// Compiles the target function and returns the main runner.
     /// Waits for the receiver before calling any of the code
     async fn compile(&self, receiver: Receiver<()>, syntax: &Arc<Mutex<Syntax>>) -> Option<T>;
-}
\ No newline at end of file
+
+    /// Compiles the target like compile, but returns the compiled IR instead of running it.
+    async fn emit_ir(&self, receiver: Receiver<()>, syntax: &Arc<Mutex<Syntax>>) -> Result<String, Vec<ParsingError>>;
+}
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::Ordering;
+    use async_trait::async_trait;
+    use indexmap::IndexMap;
+    use tokio::runtime::{Builder, Runtime};
+    use crate::{FinishedTraitImplementor, Modifier, ParsingError, ProcessManager};
+    use crate::async_util::{HandleWrapper, NameResolver};
+    use crate::function::{CodeBody, CodelessFinalizedFunction, FinalizedFunction, UnfinalizedFunction};
+    use crate::r#struct::{FinalizedStruct, StructData, UnfinalizedStruct};
+    use crate::syntax::Syntax;
+    use crate::types::FinalizedTypes;
+
+    /// A process manager for tests which only use the solver, so nothing is ever verified.
+    #[derive(Clone)]
+    struct SolverOnly {
+        handle: Arc<Mutex<HandleWrapper>>,
+        generics: HashMap<String, FinalizedTypes>,
+    }
+
+    #[async_trait]
+    impl ProcessManager for SolverOnly {
+        fn handle(&self) -> &Arc<Mutex<HandleWrapper>> {
+            return &self.handle;
+        }
+
+        async fn verify_func(&self, _function: UnfinalizedFunction, _syntax: &Arc<Mutex<Syntax>>) -> (CodelessFinalizedFunction, CodeBody) {
+            unreachable!()
+        }
+
+        async fn verify_code(&self, _function: CodelessFinalizedFunction, _code: CodeBody, _resolver: Box<dyn NameResolver>,
+                             _syntax: &Arc<Mutex<Syntax>>) -> FinalizedFunction {
+            unreachable!()
+        }
+
+        async fn verify_struct(&self, _structure: UnfinalizedStruct, _resolver: Box<dyn NameResolver>,
+                               _syntax: &Arc<Mutex<Syntax>>) -> FinalizedStruct {
+            unreachable!()
+        }
+
+        fn generics(&self) -> &HashMap<String, FinalizedTypes> {
+            return &self.generics;
+        }
+
+        fn mut_generics(&mut self) -> &mut HashMap<String, FinalizedTypes> {
+            return &mut self.generics;
+        }
+
+        fn cloned(&self) -> Box<dyn ProcessManager> {
+            return Box::new(self.clone());
+        }
+    }
+
+    /// Adds an empty structure or trait to the syntax.
+    fn add_struct(syntax: &Arc<Mutex<Syntax>>, name: &str, modifiers: u8) -> FinalizedTypes {
+        let data = Arc::new(StructData::new(vec!(), vec!(), modifiers, name.to_string()));
+        Syntax::add(syntax, ParsingError::empty(), &data);
+        return FinalizedTypes::Struct(Arc::new(FinalizedStruct {
+            generics: IndexMap::new(),
+            fields: vec!(),
+            data,
+        }), None);
+    }
+
+    fn implement(syntax: &mut Syntax, generics: IndexMap<String, Vec<FinalizedTypes>>, target: &FinalizedTypes, base: FinalizedTypes) {
+        syntax.implementations.push(FinishedTraitImplementor {
+            chalk_type: Arc::new(Syntax::make_impldatum(&generics, target, &base)),
+            target: target.clone(),
+            base,
+            generics,
+            attributes: vec!(),
+            functions: vec!(),
+        });
+    }
+
+    /// Makes an empty syntax for the solver. The runtime has to be kept alive while it's used.
+    fn solver_syntax() -> (Runtime, Arc<Mutex<Syntax>>) {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let handle = Arc::new(Mutex::new(HandleWrapper {
+            handle: runtime.handle().clone(),
+            joining: vec!(),
+            names: Default::default(),
+            waker: None
+        }));
+        let syntax = Arc::new(Mutex::new(Syntax::new(Box::new(SolverOnly { handle, generics: HashMap::new() }))));
+        return (runtime, syntax);
+    }
+
+    #[test]
+    pub fn test_where_clauses() {
+        let (_runtime, syntax) = solver_syntax();
+
+        let traits = Modifier::Public as u8 | Modifier::Trait as u8;
+        let first = add_struct(&syntax, "test::First", traits);
+        let second = add_struct(&syntax, "test::Second", traits);
+        let bounded = add_struct(&syntax, "test::Bounded", Modifier::Public as u8);
+        let unbounded = add_struct(&syntax, "test::Unbounded", Modifier::Public as u8);
+
+        let mut locked = syntax.lock().unwrap();
+        // impl First for Bounded
+        implement(&mut locked, IndexMap::new(), &first, bounded.clone());
+        // impl<T: First> Second for T
+        let mut generics = IndexMap::new();
+        generics.insert("T".to_string(), vec!(first.clone()));
+        implement(&mut locked, generics, &second, FinalizedTypes::Generic("T".to_string(), vec!(first.clone())));
+
+        assert!(locked.solve(&bounded, &second));
+        // Unbounded doesn't implement First, so the generic implementation doesn't apply.
+        assert!(!locked.solve(&unbounded, &second));
+    }
+
+    #[test]
+    pub fn test_solver_limits() {
+        let (_runtime, syntax) = solver_syntax();
+        let traits = Modifier::Public as u8 | Modifier::Trait as u8;
+        let target = add_struct(&syntax, "test::Target", Modifier::Public as u8);
+        let mut chain = vec!(add_struct(&syntax, "test::Trait0", traits));
+        for i in 1..40 {
+            chain.push(add_struct(&syntax, &format!("test::Trait{}", i), traits));
+        }
+
+        let mut locked = syntax.lock().unwrap();
+        // impl Trait0 for Target
+        implement(&mut locked, IndexMap::new(), &chain[0], target.clone());
+        // impl<T: TraitN> TraitN+1 for T, so each trait in the chain is one level deeper than the last.
+        for i in 1..chain.len() {
+            let mut generics = IndexMap::new();
+            generics.insert("T".to_string(), vec!(chain[i - 1].clone()));
+            implement(&mut locked, generics, &chain[i], FinalizedTypes::Generic("T".to_string(), vec!(chain[i - 1].clone())));
+        }
+
+        // The chain is deeper than the default limit of 30, so it's only found with a higher one.
+        locked.set_solver_limits(100, 3000);
+        assert!(locked.solve(&target, chain.last().unwrap()));
+    }
+
+    #[test]
+    pub fn test_solve_cache() {
+        let (_runtime, syntax) = solver_syntax();
+        let traits = Modifier::Public as u8 | Modifier::Trait as u8;
+        let first = add_struct(&syntax, "test::First", traits);
+        let second = add_struct(&syntax, "test::Second", traits);
+        let target = add_struct(&syntax, "test::Target", Modifier::Public as u8);
+
+        let mut locked = syntax.lock().unwrap();
+        // impl First for Target
+        implement(&mut locked, IndexMap::new(), &first, target.clone());
+        // impl<T: First> Second for T, which isn't direct so it needs Chalk.
+        let mut generics = IndexMap::new();
+        generics.insert("T".to_string(), vec!(first.clone()));
+        implement(&mut locked, generics, &second, FinalizedTypes::Generic("T".to_string(), vec!(first.clone())));
+
+        // Implementations can still be added, so nothing is cached yet.
+        assert!(locked.solve(&target, &second));
+        assert!(locked.solve(&target, &second));
+        assert_eq!(locked.solver_calls.load(Ordering::Relaxed), 2);
+
+        locked.async_manager.finished = true;
+        assert!(locked.finished_impls());
+        assert!(locked.solve(&target, &second));
+        assert!(locked.solve(&target, &second));
+        assert_eq!(locked.solver_calls.load(Ordering::Relaxed), 3);
+    }
+}
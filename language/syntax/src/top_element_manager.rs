@@ -69,15 +69,20 @@ impl<T: Future<Output=Result<FinalizedEffects, ParsingError>>, F: Fn(Arc<Functio
             Poll::Ready(inner) => match inner {
                 Ok(inner) => match inner {
                     Some(found) => {
+                        let mut last_error = None;
                         for trying in found {
                             match pin!((self.checker)(trying)).poll(cx) {
                                 Poll::Ready(found) => match found {
                                     Ok(found) => return Poll::Ready(Ok(found)),
-                                    Err(_) => {}
+                                    Err(error) => last_error = Some(error)
                                 },
                                 Poll::Pending => return Poll::Pending
                             }
                         }
+                        // If no more implementations can be added, none of them will ever match.
+                        if self.syntax.lock().unwrap().finished_impls() {
+                            return Poll::Ready(Err(last_error.unwrap_or(self.error.clone())));
+                        }
                         self.syntax.lock().unwrap().async_manager.impl_waiters.push(cx.waker().clone());
                         Poll::Pending
                     },
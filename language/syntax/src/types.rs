@@ -543,6 +543,10 @@ impl FinalizedTypes {
                                                     generics.clone())))))
                 } else {
                     // Clone the type and add the new type to the structures.
+                    // Degeneric a copy of the structure first, so a bad generic is an error before the new type is added.
+                    let mut data = FinalizedStruct::clone(AsyncDataGetter::new(syntax.clone(), found.data.clone()).await.deref());
+                    data.degeneric(generics, syntax).await?;
+
                     let mut other = StructData::clone(&found.data);
                     other.name = name.clone();
 
@@ -562,10 +566,7 @@ impl FinalizedTypes {
                         locked.structures.types.insert(name.clone(), arc_other.clone());
                         locked.structures.sorted.push(arc_other.clone());
                     }
-                    // Get the FinalizedStruct and degeneric it.
-                    let mut data = FinalizedStruct::clone(AsyncDataGetter::new(syntax.clone(), found.data.clone()).await.deref());
                     data.data = arc_other.clone();
-                    data.degeneric(generics, syntax).await?;
                     let data = Arc::new(data);
                     // Add the flattened type to the
                     let mut locked = syntax.lock().unwrap();
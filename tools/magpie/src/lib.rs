@@ -0,0 +1,158 @@
+use core::fmt::Debug;
+use std::{env, path, ptr};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use include_dir::{Dir, DirEntry, File, include_dir};
+
+use data::{Arguments, CompilerArguments, Optimization, ParsingError, Readable, RunnerSettings, SourceSet, StringSourceSet};
+use syntax::syntax::Syntax;
+
+pub mod project;
+mod test;
+
+static CORE: Dir = include_dir!("lib/core/src");
+static STD_UNIVERSAL: Dir = include_dir!("lib/std/universal");
+static STD_WINDOWS: Dir = include_dir!("lib/std/windows");
+static STD_LINUX: Dir = include_dir!("lib/std/linux");
+static STD_MACOS: Dir = include_dir!("lib/std/macos");
+//static MAGPIE: Dir = include_dir!("tools/magpie/lib/src");
+
+pub fn build<T: Send + 'static>(arguments: &mut Arguments, mut source: Vec<Box<dyn SourceSet>>)
+    -> Result<Option<T>, ()> {
+    add_libraries(&mut source);
+    arguments.runner_settings.sources = source.iter().map(|inner| inner.cloned()).collect::<Vec<_>>();
+
+    let value = run::<T>(&arguments);
+    return match value {
+        Ok(inner) => Ok(inner),
+        Err(errors) => {
+            println!("Errors:");
+            for error in errors {
+                error.print(&source);
+            }
+            Err(())
+        },
+    }
+}
+
+/// Compiles the source string with the standard library and runs the entry function in one step.
+/// The entry is the function's name inside the source, for example "main".
+/// Returns None if the entry function doesn't exist, or the errors if the source failed to compile.
+pub fn run_source<T: Send + 'static>(source: &str, entry: &str) -> Result<Option<T>, Vec<ParsingError>> {
+    return run::<T>(&source_arguments(source, entry, Optimization::None));
+}
+
+/// Compiles the source string with the standard library like run_source, but returns the IR instead of running it.
+pub fn source_ir(source: &str, entry: &str, optimization: Optimization) -> Result<String, Vec<ParsingError>> {
+    let arguments = source_arguments(source, entry, optimization);
+    return arguments.cpu_runtime.block_on(runner::runner::emit_ir(&arguments));
+}
+
+/// Parses and verifies the source string with the standard library like run_source, but returns the syntax
+/// instead of compiling it, to look at what was parsed.
+pub fn source_syntax(source: &str) -> Result<Arc<Mutex<Syntax>>, Vec<ParsingError>> {
+    let arguments = source_arguments(source, "main", Optimization::None);
+    return arguments.cpu_runtime.block_on(runner::runner::check(&arguments));
+}
+
+/// The arguments to compile the source string with the standard library.
+fn source_arguments(source: &str, entry: &str, optimization: Optimization) -> Arguments {
+    let mut sources: Vec<Box<dyn SourceSet>> = vec!(Box::new(StringSourceSet {
+        name: "source".to_string(),
+        source: source.to_string(),
+    }));
+    add_libraries(&mut sources);
+
+    return Arguments::build_args(false, RunnerSettings {
+        sources,
+        debug: false,
+        compiler_arguments: CompilerArguments {
+            target: format!("source::{}", entry),
+            compiler: "llvm".to_string(),
+            temp_folder: env::temp_dir().join("raven"),
+            verify: cfg!(debug_assertions),
+            optimization,
+            max_nesting: None,
+            solver_overflow_depth: None,
+            solver_max_size: None
+        },
+        timings: None
+    });
+}
+
+/// Adds the core and standard libraries for the current platform to the sources.
+fn add_libraries(source: &mut Vec<Box<dyn SourceSet>>) {
+    let platform_std = match env::consts::OS {
+        "windows" => &STD_WINDOWS,
+        "linux" => &STD_LINUX,
+        "macos" => &STD_MACOS,
+        _ => panic!("Unsupported platform {}!", env::consts::OS)
+    };
+
+    source.push(Box::new(InnerSourceSet {
+        set: &STD_UNIVERSAL
+    }));
+    source.push(Box::new(InnerSourceSet {
+        set: platform_std
+    }));
+    source.push(Box::new(InnerSourceSet {
+        set: &CORE
+    }));
+}
+
+fn run<T: Send + 'static>(arguments: &Arguments) -> Result<Option<T>, Vec<ParsingError>> {
+    let result = arguments.cpu_runtime.block_on(runner::runner::run::<AtomicPtr<T>>(&arguments))?;
+    return Ok(result.map(|inner| unsafe { ptr::read(inner.load(Ordering::Relaxed)) }));
+}
+
+#[derive(Clone, Debug)]
+pub struct InnerSourceSet {
+    set: &'static Dir<'static>,
+}
+
+// Forced to make a wrapper due to orphan rule
+pub struct FileWrapper {
+    file: &'static File<'static>,
+}
+
+impl SourceSet for InnerSourceSet {
+    fn get_files(&self) -> Vec<Box<dyn Readable>> {
+        let mut output = Vec::new();
+        read_recursive(&self.set, &mut output);
+        return output;
+    }
+
+    fn relative(&self, other: &Box<dyn Readable>) -> String {
+        let name = other.path()
+            .replace(path::MAIN_SEPARATOR, "::");
+        return name[0..name.len() - 3].to_string();
+    }
+
+    fn cloned(&self) -> Box<dyn SourceSet> {
+        return Box::new(self.clone());
+    }
+}
+
+fn read_recursive(base: &Dir<'static>, output: &mut Vec<Box<dyn Readable>>) {
+    for entry in base.entries() {
+        match entry {
+            DirEntry::Dir(directory) => {
+                read_recursive(directory, output);
+            }
+            DirEntry::File(file) => {
+                output.push(Box::new(FileWrapper { file }))
+            }
+        }
+    }
+}
+
+impl Readable for FileWrapper {
+    fn read(&self) -> String {
+        return self.file.contents_utf8().unwrap().to_string();
+    }
+
+    fn path(&self) -> String {
+        return self.file.path().to_str().unwrap().to_string();
+    }
+}
\ No newline at end of file
@@ -1,19 +1,7 @@
-use core::fmt::Debug;
-use std::{env, path, ptr};
-use std::sync::atomic::{AtomicPtr, Ordering};
+use std::{env, path};
 
-use include_dir::{Dir, DirEntry, File, include_dir};
-
-use data::{Arguments, CompilerArguments, FileSourceSet, ParsingError, Readable, RunnerSettings, SourceSet};
-
-pub mod project;
-mod test;
-static CORE: Dir = include_dir!("lib/core/src");
-static STD_UNIVERSAL: Dir = include_dir!("lib/std/universal");
-static STD_WINDOWS: Dir = include_dir!("lib/std/windows");
-static STD_LINUX: Dir = include_dir!("lib/std/linux");
-static STD_MACOS: Dir = include_dir!("lib/std/macos");
-//static MAGPIE: Dir = include_dir!("tools/magpie/lib/src");
+use data::{Arguments, CompilerArguments, FileSourceSet, Optimization, RunnerSettings};
+use magpie::build;
 
 fn main() {
     let args = env::args().collect::<Vec<_>>();
@@ -26,8 +14,14 @@ fn main() {
             compiler_arguments: CompilerArguments {
                 target: format!("{}::main", args[1].clone().split(path::MAIN_SEPARATOR).last().unwrap().replace(".rv", "")),
                 compiler: "llvm".to_string(),
-                temp_folder: env::current_dir().unwrap().join("target")
-            }
+                temp_folder: env::current_dir().unwrap().join("target"),
+                verify: cfg!(debug_assertions),
+                optimization: Optimization::None,
+                max_nesting: None,
+                solver_overflow_depth: None,
+                solver_max_size: None
+            },
+            timings: None
         });
 
         println!("Building and running {}...", args[1].clone().split(path::MAIN_SEPARATOR).last().unwrap().replace(".rv", ""));
@@ -53,8 +47,14 @@ fn main() {
         compiler_arguments: CompilerArguments {
             target: "build::project".to_string(),
             compiler: "llvm".to_string(),
-            temp_folder: env::current_dir().unwrap().join("target")
-        }
+            temp_folder: env::current_dir().unwrap().join("target"),
+            verify: cfg!(debug_assertions),
+            optimization: Optimization::None,
+            max_nesting: None,
+            solver_overflow_depth: None,
+            solver_max_size: None
+        },
+        timings: None
     });
 
     println!("Setting up build...");
@@ -85,93 +85,3 @@ fn main() {
         _ => {}
     }
 }
-
-pub fn build<T: Send + 'static>(arguments: &mut Arguments, mut source: Vec<Box<dyn SourceSet>>)
-    -> Result<Option<T>, ()> {
-    let platform_std = match env::consts::OS {
-        "windows" => &STD_WINDOWS,
-        "linux" => &STD_LINUX,
-        "macos" => &STD_MACOS,
-        _ => panic!("Unsupported platform {}!", env::consts::OS)
-    };
-
-    source.push(Box::new(InnerSourceSet {
-        set: &STD_UNIVERSAL
-    }));
-    source.push(Box::new(InnerSourceSet {
-        set: platform_std
-    }));
-    source.push(Box::new(InnerSourceSet {
-        set: &CORE
-    }));
-
-    arguments.runner_settings.sources = source.iter().map(|inner| inner.cloned()).collect::<Vec<_>>();
-
-    let value = run::<T>(&arguments);
-    return match value {
-        Ok(inner) => Ok(inner),
-        Err(errors) => {
-            println!("Errors:");
-            for error in errors {
-                error.print(&source);
-            }
-            Err(())
-        },
-    }
-}
-
-fn run<T: Send + 'static>(arguments: &Arguments) -> Result<Option<T>, Vec<ParsingError>> {
-    let result = arguments.cpu_runtime.block_on(runner::runner::run::<AtomicPtr<T>>(&arguments))?;
-    return Ok(result.map(|inner| unsafe { ptr::read(inner.load(Ordering::Relaxed)) }));
-}
-
-#[derive(Clone, Debug)]
-pub struct InnerSourceSet {
-    set: &'static Dir<'static>,
-}
-
-// Forced to make a wrapper due to orphan rule
-pub struct FileWrapper {
-    file: &'static File<'static>,
-}
-
-impl SourceSet for InnerSourceSet {
-    fn get_files(&self) -> Vec<Box<dyn Readable>> {
-        let mut output = Vec::new();
-        read_recursive(&self.set, &mut output);
-        return output;
-    }
-
-    fn relative(&self, other: &Box<dyn Readable>) -> String {
-        let name = other.path()
-            .replace(path::MAIN_SEPARATOR, "::");
-        return name[0..name.len() - 3].to_string();
-    }
-
-    fn cloned(&self) -> Box<dyn SourceSet> {
-        return Box::new(self.clone());
-    }
-}
-
-fn read_recursive(base: &Dir<'static>, output: &mut Vec<Box<dyn Readable>>) {
-    for entry in base.entries() {
-        match entry {
-            DirEntry::Dir(directory) => {
-                read_recursive(directory, output);
-            }
-            DirEntry::File(file) => {
-                output.push(Box::new(FileWrapper { file }))
-            }
-        }
-    }
-}
-
-impl Readable for FileWrapper {
-    fn read(&self) -> String {
-        return self.file.contents_utf8().unwrap().to_string();
-    }
-
-    fn path(&self) -> String {
-        return self.file.path().to_str().unwrap().to_string();
-    }
-}
\ No newline at end of file
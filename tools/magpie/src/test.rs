@@ -6,9 +6,19 @@ use crate::FileWrapper;
 #[cfg(test)]
 mod test {
     use std::{env, path};
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::Ordering;
     use include_dir::{Dir, DirEntry, include_dir};
-    use data::{Arguments, CompilerArguments, RunnerSettings};
-    use crate::build;
+    use checker::output::TypesChecker;
+    use data::{Arguments, CompilerArguments, Optimization, PHASES, RunnerSettings, SourceSet, StringSourceSet, Timings};
+    use parser::{IncrementalParse, parse_incremental};
+    use parser::parser::util::MAX_NESTING_DEPTH;
+    use syntax::async_util::HandleWrapper;
+    use syntax::interface::emit_interface;
+    use syntax::syntax::Syntax;
+    use syntax::types::FinalizedTypes;
+    use tokio::runtime::{Builder, Runtime};
+    use crate::{add_libraries, build, run, run_source, source_ir, source_syntax};
     use crate::test::InnerFileSourceSet;
 
     static TESTS: Dir = include_dir!("lib/test/test");
@@ -18,6 +28,388 @@ mod test {
         test_recursive(&TESTS);
     }
 
+    #[test]
+    pub fn test_run_source() {
+        match run_source::<bool>("fn main() -> bool {\n    return 1 + 2 == 3;\n}", "main") {
+            Ok(found) => assert_eq!(found, Some(true)),
+            Err(_) => assert!(false, "Failed to compile the source!")
+        }
+    }
+
+    #[test]
+    pub fn test_init_order() {
+        let ir = source_ir("#[init(2)]\nfn second() {}\n\n#[init(1)]\nfn first() {}\n\n#[init(1)]\nfn after_first() {}\n\n\
+            fn main() -> bool {\n    return true;\n}", "main", Optimization::None).unwrap();
+        let constructors = ir.lines().find(|line| line.starts_with("@llvm.global_ctors")).unwrap();
+        let first = constructors.find("i32 102, void ()* @\"source::after_first\"").unwrap();
+        let second = constructors.find("i32 102, void ()* @\"source::first\"").unwrap();
+        let third = constructors.find("i32 103, void ()* @\"source::second\"").unwrap();
+        assert!(first < second && second < third, "Init functions are out of order: {}", constructors);
+    }
+
+    #[test]
+    pub fn test_init_errors() {
+        let errors = source_ir("#[init]\nfn first() {\n    second();\n}\n\n#[init]\nfn second() {\n    first();\n}\n\n\
+            #[init(-1)]\nfn negative() {}\n\nfn main() -> bool {\n    return true;\n}", "main", Optimization::None).unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Init functions source::first and source::second depend on each other!"));
+        assert!(errors.iter().any(|error| error.message.starts_with("Init function source::negative has priority -1")));
+    }
+
+    #[test]
+    pub fn test_recursive_structures() {
+        let errors = run_source::<bool>("struct First {\n    second: Second;\n}\n\nstruct Second {\n    first: [First];\n}\n\n\
+            fn main() -> bool {\n    return true;\n}", "main").unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Recursive structure source::First has infinite size because of field second!" ||
+            error.message == "Recursive structure source::Second has infinite size because of field first!"));
+    }
+
+    #[test]
+    pub fn test_constant_folding() {
+        let ir = source_ir("fn main() -> u64 {\n    return 2000000000 + 2000000000;\n}", "main", Optimization::None).unwrap();
+        assert!(ir.contains("i64 4000000000"), "Constant wasn't folded: {}", ir);
+        assert!(!ir.contains("math::Add"), "Constant wasn't folded: {}", ir);
+
+        let errors = run_source::<u64>("fn main() -> u64 {\n    return 18446744073709551615 + 1;\n}", "main").unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Constant 18446744073709551615 + 1 overflows a u64!"));
+    }
+
+    #[test]
+    pub fn test_timings() {
+        let mut sources: Vec<Box<dyn SourceSet>> = vec!(Box::new(StringSourceSet {
+            name: "source".to_string(),
+            source: "fn main() -> bool {\n    return 1 + 2 == 3;\n}".to_string(),
+        }));
+        add_libraries(&mut sources);
+        let timings = Arc::new(Mutex::new(Timings::default()));
+        let arguments = Arguments::build_args(false, RunnerSettings {
+            sources,
+            debug: false,
+            compiler_arguments: CompilerArguments {
+                target: "source::main".to_string(),
+                compiler: "llvm".to_string(),
+                temp_folder: env::temp_dir().join("raven"),
+                verify: true,
+                optimization: Optimization::None,
+                max_nesting: None,
+                solver_overflow_depth: None,
+                solver_max_size: None
+            },
+            timings: Some(timings.clone())
+        });
+
+        assert_eq!(run::<bool>(&arguments).unwrap(), Some(true));
+        let timings = timings.lock().unwrap();
+        assert_eq!(timings.phases.iter().map(|(phase, _)| phase.as_str()).collect::<Vec<_>>(), PHASES.to_vec());
+        for (phase, time) in &timings.phases {
+            assert!(!time.is_zero(), "Phase {} wasn't timed!", phase);
+        }
+    }
+
+    #[test]
+    pub fn test_select() {
+        let ir = source_ir("fn main() -> u64 {\n    let found = 0;\n    if true {\n        found = 5;\n    } else {\n        found = 10;\n    }\n\
+            return found;\n}", "main", Optimization::None).unwrap();
+        assert!(ir.contains(" = select i1 "), "If wasn't lowered to a select: {}", ir);
+
+        // Branches with side effects have to stay branches.
+        let ir = source_ir("fn other() -> u64 {\n    return 5;\n}\n\nfn main() -> u64 {\n    let found = 0;\n    if true {\n\
+            found = other();\n    } else {\n        found = 10;\n    }\n    return found;\n}", "main", Optimization::None).unwrap();
+        assert!(!ir.contains(" = select i1 "), "If with side effects was lowered to a select: {}", ir);
+    }
+
+    #[test]
+    pub fn test_cold_functions() {
+        let ir = source_ir("#[cold]\nfn rarely() {}\n\nfn main() -> bool {\n    rarely();\n    return true;\n}",
+                           "main", Optimization::None).unwrap();
+        assert!(ir.lines().any(|line| line.starts_with("attributes #") && line.contains("cold")),
+                "Function isn't cold: {}", ir);
+
+        let errors = source_ir("#[cold]\n#[hot]\nfn both() {}\n\nfn main() -> bool {\n    both();\n    return true;\n}",
+                               "main", Optimization::None).unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Function source::both can't be both cold and hot!"));
+    }
+
+    #[test]
+    pub fn test_generic_linkage() {
+        let ir = source_ir("fn identity<T>(value: T) -> T {\n    return value;\n}\n\n\
+            fn main() -> u64 {\n    return identity(5);\n}", "main", Optimization::None).unwrap();
+        let defined = |name: &str| ir.lines().find(|line| line.starts_with("define") && line.contains(name)).unwrap();
+        assert!(defined("@\"source::identity$u64\"").starts_with("define linkonce_odr "), "{}", ir);
+        assert!(!defined("@\"source::main\"").contains("linkonce_odr"), "{}", ir);
+    }
+
+    #[test]
+    pub fn test_optimization() {
+        let source = "fn main() -> u64 {\n    let first = 2;\n    let second = first * 3;\n    return second + 4;\n}";
+        // Counts the instructions in main's body, which are the indented lines of its definition.
+        let instructions = |optimization| {
+            let ir = source_ir(source, "main", optimization).unwrap();
+            return ir.lines().skip_while(|line| !(line.starts_with("define") && line.contains("@\"source::main\"")))
+                .take_while(|line| *line != "}").filter(|line| line.starts_with("  ")).count();
+        };
+        let unoptimized = instructions(Optimization::None);
+        let optimized = instructions(Optimization::Aggressive);
+        assert!(optimized < unoptimized, "Optimizing went from {} to {} instructions", unoptimized, optimized);
+    }
+
+    #[test]
+    pub fn test_string_deduplication() {
+        let ir = source_ir("fn first() -> str {\n    return \"hello\";\n}\n\nfn second() -> str {\n    return \"hello\";\n}\n\n\
+            fn main() -> bool {\n    first();\n    second();\n    return true;\n}", "main", Optimization::None).unwrap();
+        let globals = ir.lines().filter(|line| line.starts_with("@str.") && line.contains("c\"hello\\00\"")).count();
+        assert_eq!(globals, 1, "{}", ir);
+    }
+
+    #[test]
+    pub fn test_casts() {
+        let cast = |body: &str| format!("import numbers::Cast;\n\nfn main() -> u64 {{\n{}\n}}", body);
+        // Narrowing wraps and unsigned numbers are zero extended at runtime.
+        assert_eq!(run_source::<u64>(&cast("    let value = 300;\n    return value.cast<u8>().cast<u64>();"), "main").unwrap(), Some(44));
+        assert_eq!(run_source::<u64>(&cast("    let value = 200;\n    return value.cast<u8>().cast<u64>();"), "main").unwrap(), Some(200));
+        // Signed numbers are sign extended.
+        assert_eq!(run_source::<i64>("import numbers::Cast;\n\nfn main() -> i64 {\n    let value = 200;\n\
+            return value.cast<i8>().cast<i64>();\n}", "main").unwrap(), Some(-56));
+        // Floats are truncated, and unsigned targets can hold more than an i64.
+        assert_eq!(run_source::<u64>(&cast("    let value = 300.7;\n    return value.cast<u64>();"), "main").unwrap(), Some(300));
+        assert_eq!(run_source::<u64>(&cast("    let value = 10000000000000000000.0;\n    return value.cast<u64>();"), "main").unwrap(),
+                   Some(10000000000000000000));
+
+        // Constant casts are folded to the same values.
+        let ir = source_ir(&cast("    return 300.cast<u8>().cast<u64>();"), "main", Optimization::None).unwrap();
+        assert!(ir.contains("i64 44") && !ir.contains("numbers::Cast"), "Cast wasn't folded: {}", ir);
+        let ir = source_ir(&cast("    return 300.7.cast<u64>();"), "main", Optimization::None).unwrap();
+        assert!(ir.contains("i64 300") && !ir.contains("numbers::Cast"), "Cast wasn't folded: {}", ir);
+        let errors = source_ir(&cast("    return 1e30.cast<u64>();"), "main", Optimization::None).unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Constant 1000000000000000000000000000000 doesn't fit in a u64!"));
+    }
+
+    #[test]
+    pub fn test_nesting_limit() {
+        // Too much nesting is an error instead of overflowing the stack, whether it's parenthesis or a chain of operators.
+        let nested = format!("fn main() -> u64 {{\n    return {}1{};\n}}", "(".repeat(10000), ")".repeat(10000));
+        let errors = run_source::<u64>(&nested, "main").unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Expression nesting too deep!"), "{:?}", errors);
+        let chained = format!("fn main() -> u64 {{\n    return 1{};\n}}", " + 1".repeat(10000));
+        let errors = run_source::<u64>(&chained, "main").unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Expression nesting too deep!"), "{:?}", errors);
+
+        // The limit can be lowered.
+        let source = "fn main() -> u64 {\n    return ((((((1))))));\n}";
+        assert_eq!(run_source::<u64>(source, "main").unwrap(), Some(1));
+        let mut sources: Vec<Box<dyn SourceSet>> = vec!(Box::new(StringSourceSet {
+            name: "source".to_string(),
+            source: source.to_string(),
+        }));
+        add_libraries(&mut sources);
+        let arguments = Arguments::build_args(false, RunnerSettings {
+            sources,
+            debug: false,
+            compiler_arguments: CompilerArguments {
+                target: "source::main".to_string(),
+                compiler: "llvm".to_string(),
+                temp_folder: env::temp_dir().join("raven"),
+                verify: true,
+                optimization: Optimization::None,
+                max_nesting: Some(4),
+                solver_overflow_depth: None,
+                solver_max_size: None
+            },
+            timings: None
+        });
+        let errors = run::<u64>(&arguments).unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Expression nesting too deep!"), "{:?}", errors);
+    }
+
+    #[test]
+    pub fn test_overlapping_implementations() {
+        // A blanket implementation with no bounds covers Point, so it conflicts with Point's own implementation.
+        let errors = run_source::<bool>("import math::Add;\n\nstruct Point {}\n\n\
+            impl<T> Add<T, T> for T {\n    fn add(self, other: T) -> T {\n        return self;\n    }\n}\n\n\
+            impl Add<Point, Point> for Point {\n    fn add(self, other: Point) -> Point {\n        return self;\n    }\n}\n\n\
+            fn main() -> bool {\n    return true;\n}", "main").unwrap_err();
+        assert!(errors.iter().any(|error| error.message.starts_with("Conflicting implementations of ") &&
+            error.message.ends_with(" for source::Point")), "{:?}", errors);
+    }
+
+    /// Gets the finalized type of a structure or trait.
+    fn finalized_type(syntax: &Syntax, name: &str) -> FinalizedTypes {
+        let data = syntax.structures.types.get(name).unwrap();
+        return FinalizedTypes::Struct(syntax.structures.data.get(data).unwrap().clone(), None);
+    }
+
+    /// Makes an empty syntax to parse parts of a file into. Nothing is verified unless the runtime is run,
+    /// but it has to be kept alive while parsing.
+    fn empty_syntax() -> (Runtime, Arc<Mutex<HandleWrapper>>, Arc<Mutex<Syntax>>) {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let handle = Arc::new(Mutex::new(HandleWrapper {
+            handle: runtime.handle().clone(),
+            joining: vec!(),
+            names: Default::default(),
+            waker: None
+        }));
+        let syntax = Arc::new(Mutex::new(Syntax::new(Box::new(TypesChecker::new(handle.clone(), false)))));
+        return (runtime, handle, syntax);
+    }
+
+    #[test]
+    pub fn test_direct_implementation() {
+        let syntax = source_syntax("trait First {}\n\ntrait Second {}\n\nstruct Third {}\n\nimpl First for Third {}\n").unwrap();
+        let locked = syntax.lock().unwrap();
+        let third = finalized_type(&locked, "source::Third");
+        // The libraries are checked too, so only count calls from here on.
+        let calls = locked.solver_calls.load(Ordering::Relaxed);
+
+        // A direct implementation is found without Chalk.
+        assert!(locked.solve(&third, &finalized_type(&locked, "source::First")));
+        assert_eq!(locked.solver_calls.load(Ordering::Relaxed), calls);
+
+        // Anything else still has to ask Chalk.
+        assert!(!locked.solve(&third, &finalized_type(&locked, "source::Second")));
+        assert_eq!(locked.solver_calls.load(Ordering::Relaxed), calls + 1);
+    }
+
+    #[test]
+    pub fn test_conflicting_implementations() {
+        let errors = run_source::<bool>("trait First {}\n\nstruct Second {}\n\nimpl First for Second {}\n\nimpl First for Second {}\n\n\
+            fn main() -> bool {\n    return true;\n}", "main").unwrap_err();
+        let error = errors.iter()
+            .find(|error| error.message == "Conflicting implementations of source::First for source::Second").unwrap();
+        // Either implementation can finish second, so the error can be on either one.
+        assert_eq!(error.file, "source");
+        assert!(error.start.0 == 5 || error.start.0 == 7, "Error isn't on an implementation: {:?}", error);
+    }
+
+    #[test]
+    pub fn test_interface() {
+        let syntax = source_syntax("pub struct Inner {}\n\npub struct Outer {\n    pub shown: Inner;\n    hidden: Inner;\n}\n\n\
+            struct Private {}\n\npub fn first(value: Outer) -> Inner {\n    return value.shown;\n}\n\nfn second() {}\n").unwrap();
+        let interface = emit_interface(&syntax.lock().unwrap(), "source");
+
+        assert!(interface.contains("pub struct source::Outer { pub shown: source::Inner }\n"), "{}", interface);
+        assert!(interface.contains("pub fn source::first(value: source::Outer) -> source::Inner;\n"), "{}", interface);
+        // Private items and code are left out.
+        for hidden in ["hidden", "Private", "second", "return"] {
+            assert!(!interface.contains(hidden), "Interface has {}: {}", hidden, interface);
+        }
+    }
+
+    #[test]
+    pub fn test_struct_bounds() {
+        let source = "trait Shape {}\n\nstruct Square {}\n\nimpl Shape for Square {}\n\nstruct Circle {}\n\nstruct Holder<T: Shape> {}\n\n";
+        source_syntax(&format!("{}fn first() {{\n    let holder = new Holder<Square> {{}};\n}}\n", source)).unwrap();
+
+        let errors = source_syntax(&format!("{}fn first() {{\n    let holder = new Holder<Circle> {{}};\n}}\n", source)).unwrap_err();
+        assert!(errors.iter()
+            .any(|error| error.message == "source::Circle doesn't implement source::Shape, required by T of source::Holder!"));
+    }
+
+    #[test]
+    pub fn test_mixed_switch() {
+        let errors = source_syntax("fn first(value: u64) {\n    switch value {\n        1 => {}\n        true => {}\n    }\n}\n").unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Switch arms must all be integers or all be booleans!"));
+    }
+
+    #[test]
+    pub fn test_unfinished_function() {
+        let (_runtime, handle, syntax) = empty_syntax();
+        match parse_incremental(syntax.clone(), handle, "test".to_string(), "fn first() {}\n\nfn second() {\n    return",
+                                None, MAX_NESTING_DEPTH) {
+            IncrementalParse::NeedsMoreInput(_) => {}
+            IncrementalParse::Complete(_) => assert!(false, "Unfinished function was parsed as complete!")
+        }
+
+        // Everything before the unfinished function is still parsed.
+        let locked = syntax.lock().unwrap();
+        assert!(locked.functions.types.contains_key("test::first"));
+        assert!(!locked.functions.types.contains_key("test::second"));
+        assert!(locked.errors.is_empty());
+    }
+
+    #[test]
+    pub fn test_resume() {
+        let (_runtime, handle, syntax) = empty_syntax();
+        let source = "fn first() {}\n\nfn second() {\n    return;\n}\n";
+        // The source is added in pieces, ending mid-token and then mid-function, like lines typed into a shell.
+        let mut state = None;
+        for end in [21, 30] {
+            match parse_incremental(syntax.clone(), handle.clone(), "test".to_string(), &source[..end], state.as_ref(), MAX_NESTING_DEPTH) {
+                IncrementalParse::NeedsMoreInput(next) => state = Some(next),
+                IncrementalParse::Complete(_) => assert!(false, "{:?} was parsed as complete!", &source[..end])
+            }
+        }
+
+        match parse_incremental(syntax.clone(), handle, "test".to_string(), source, state.as_ref(), MAX_NESTING_DEPTH) {
+            IncrementalParse::Complete(_) => {}
+            IncrementalParse::NeedsMoreInput(_) => assert!(false, "Finished source needs more input!")
+        }
+
+        // Each function is only parsed once, after it's finished.
+        let locked = syntax.lock().unwrap();
+        assert!(locked.functions.types.contains_key("test::first"));
+        assert!(locked.functions.types.contains_key("test::second"));
+        assert!(locked.errors.is_empty(), "{:?}", locked.errors);
+    }
+
+    #[test]
+    pub fn test_must_use() {
+        let source = "#[must_use(The value is the whole point!)]\nfn value() -> u64 {\n    return 5;\n}\n\n";
+        let syntax = source_syntax(&format!("{}fn first() {{\n    value();\n}}\n", source)).unwrap();
+        let warnings = syntax.lock().unwrap().warnings.iter().map(|warning| warning.message.clone()).collect::<Vec<_>>();
+        assert!(warnings.contains(&"Unused result of source::value which must be used! The value is the whole point!".to_string()),
+                "{:?}", warnings);
+
+        // Using the result doesn't warn.
+        let syntax = source_syntax(&format!("{}fn first() {{\n    let found = value();\n}}\n", source)).unwrap();
+        assert!(!syntax.lock().unwrap().warnings.iter().any(|warning| warning.message.contains("source::value")));
+    }
+
+    #[test]
+    pub fn test_invalid_escapes() {
+        // The error should only cover the escape, not the string before it.
+        for (string, escape, start, offsets) in [("a\\qb", "\\q", (2, 14), (33, 35)), ("ab\\u{}c", "\\u{}", (2, 15), (34, 38)),
+            // é is two bytes but one column.
+            ("é\\u{zz}", "\\u{zz}", (2, 14), (34, 40)),
+            // Hex escapes above 0x7F aren't a single byte in UTF-8.
+            ("\\x80", "\\x80", (2, 13), (32, 36))] {
+            let errors = run_source::<u64>(&format!("fn main() -> u64 {{\n    let a = \"{}\";\n    return 1;\n}}", string), "main")
+                .unwrap_err();
+            let error = errors.iter().find(|error| error.message == format!("Invalid escape sequence: {}", escape))
+                .unwrap_or_else(|| panic!("No error for {}: {:?}", escape, errors));
+            assert_eq!(error.start, start, "Wrong start for {}", escape);
+            assert_eq!(error.end.1 - error.start.1, escape.chars().count() as u32, "Wrong end for {}", escape);
+            assert_eq!((error.start_offset, error.end_offset), offsets, "Wrong offsets for {}", escape);
+        }
+    }
+
+    #[test]
+    pub fn test_return_types() {
+        let method = "trait Value {\n    fn value(self) -> u64;\n}\n\nstruct Holder {}\n\nimpl Value for Holder {\n    fn value(self) -> ";
+        assert_eq!(run_source::<u64>("fn main() -> u64 {\n    return 5;\n}", "main").unwrap(), Some(5));
+        assert_eq!(run_source::<u64>(&format!("{}u64 {{\n        return 7;\n    }}\n}}\n\n\
+            fn main() -> u64 {{\n    let holder = new Holder {{}};\n    return holder.value();\n}}", method), "main").unwrap(), Some(7));
+
+        // An arrow without a type is an error at the missing type, for functions and methods.
+        let errors = run_source::<u64>("fn main() -> {\n    return 5;\n}", "main").unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Expected a return type after ->!" && error.start == (1, 12)),
+                "{:?}", errors);
+        let errors = run_source::<u64>(&format!("{}{{\n        return 7;\n    }}\n}}\n", method), "main").unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Expected a return type after ->!" && error.start == (8, 21)),
+                "{:?}", errors);
+    }
+
+    #[test]
+    pub fn test_receivers() {
+        let source = |receiver: &str| format!("trait Value {{\n    fn value({0}) -> u64;\n}}\n\nstruct Holder {{}}\n\n\
+            impl Value for Holder {{\n    fn value({0}) -> u64 {{\n        return 7;\n    }}\n}}\n\n\
+            fn main() -> u64 {{\n    let holder = new Holder {{}};\n    return holder.value();\n}}", receiver);
+        for receiver in ["self", "&self", "&mut self"] {
+            assert_eq!(run_source::<u64>(&source(receiver), "main").unwrap(), Some(7), "Failed for {}", receiver);
+        }
+
+        let errors = run_source::<u64>(&source("&mutself"), "main").unwrap_err();
+        assert!(errors.iter().any(|error| error.message == "Expected self, &self, or &mut self, found &mutself"), "{:?}", errors);
+    }
+
     fn test_recursive(dir: &'static Dir<'_>) {
         for entry in dir.entries() {
             match entry {
@@ -35,8 +427,14 @@ mod test {
                         compiler_arguments: CompilerArguments {
                             compiler: "llvm".to_string(),
                             target: path.clone(),
-                            temp_folder: env::current_dir().unwrap().join("target")
-                        }
+                            temp_folder: env::current_dir().unwrap().join("target"),
+                            verify: cfg!(debug_assertions),
+                            optimization: Optimization::None,
+                            max_nesting: None,
+                            solver_overflow_depth: None,
+                            solver_max_size: None
+                        },
+                        timings: None
                     });
 
                     match build::<bool>(&mut arguments, vec!(Box::new(InnerFileSourceSet {